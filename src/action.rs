@@ -0,0 +1,468 @@
+use bevy::{input::InputSystem, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    console::{ConsoleAppExt, ConsoleState},
+    inspector::InspectorState,
+    settings::{ControlScheme, Settings, SettingsFile},
+};
+
+// Analog values above this count as the action being pressed.
+const PRESS_THRESHOLD: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Thrust,
+    Reverse,
+    TurnLeft,
+    TurnRight,
+    RollLeft,
+    RollRight,
+    Fire,
+    Shield,
+    Hyperspace,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Thrust,
+        Action::Reverse,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::RollLeft,
+        Action::RollRight,
+        Action::Fire,
+        Action::Shield,
+        Action::Hyperspace,
+    ];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    // Triggers report how far they are pulled, other buttons are either 0 or 1.
+    Button(GamepadButtonType),
+    // One half of a stick axis, `positive` picks which half.
+    Axis {
+        axis: GamepadAxisType,
+        positive: bool,
+    },
+}
+
+// Keyboard keys for the first two players, every player gets the same layout on their own pad.
+const PLAYER_KEYS: [[(Action, KeyCode); 9]; 2] = [
+    [
+        (Action::Thrust, KeyCode::W),
+        (Action::Reverse, KeyCode::S),
+        (Action::TurnLeft, KeyCode::A),
+        (Action::TurnRight, KeyCode::D),
+        (Action::RollLeft, KeyCode::ShiftLeft),
+        (Action::RollRight, KeyCode::ControlLeft),
+        (Action::Fire, KeyCode::Space),
+        (Action::Shield, KeyCode::E),
+        (Action::Hyperspace, KeyCode::H),
+    ],
+    [
+        (Action::Thrust, KeyCode::Up),
+        (Action::Reverse, KeyCode::Down),
+        (Action::TurnLeft, KeyCode::Left),
+        (Action::TurnRight, KeyCode::Right),
+        (Action::RollLeft, KeyCode::Comma),
+        (Action::RollRight, KeyCode::Period),
+        (Action::Fire, KeyCode::ControlRight),
+        (Action::Shield, KeyCode::ShiftRight),
+        (Action::Hyperspace, KeyCode::Return),
+    ],
+];
+
+pub fn default_bindings(player: usize) -> Vec<(Action, Binding)> {
+    use Binding::*;
+    use GamepadAxisType::*;
+    use GamepadButtonType::*;
+
+    let mut bindings: Vec<(Action, Binding)> = PLAYER_KEYS
+        .get(player)
+        .into_iter()
+        .flatten()
+        .map(|&(action, key)| (action, Key(key)))
+        .collect();
+    if player == 0 {
+        bindings.push((Action::Fire, Mouse(MouseButton::Left)));
+    }
+    let axis = |axis, positive| Axis { axis, positive };
+    bindings.extend([
+        (Action::Thrust, Button(RightTrigger2)),
+        (Action::Thrust, axis(LeftStickY, true)),
+        (Action::Reverse, Button(LeftTrigger2)),
+        (Action::Reverse, axis(LeftStickY, false)),
+        (Action::TurnLeft, axis(LeftStickX, false)),
+        (Action::TurnRight, axis(LeftStickX, true)),
+        (Action::RollLeft, Button(LeftTrigger)),
+        (Action::RollRight, Button(RightTrigger)),
+        (Action::Fire, Button(South)),
+        (Action::Shield, Button(West)),
+        (Action::Hyperspace, Button(North)),
+    ]);
+    bindings
+}
+
+// How far each action is pushed this frame, from 0 to 1, and last frame.
+#[derive(Debug, Default, Clone)]
+pub struct ActionState {
+    values: HashMap<Action, f32>,
+    previous: HashMap<Action, f32>,
+}
+
+impl ActionState {
+    pub fn value(&self, action: Action) -> f32 {
+        self.values.get(&action).copied().unwrap_or_default()
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.value(action) > PRESS_THRESHOLD
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        let previous = self.previous.get(&action).copied().unwrap_or_default();
+        self.pressed(action) && previous <= PRESS_THRESHOLD
+    }
+
+    // -1 when only `negative` is pushed, 1 when only `positive` is.
+    pub fn axis(&self, negative: Action, positive: Action) -> f32 {
+        self.value(positive) - self.value(negative)
+    }
+
+    // The actions that are pushed at all, e.g. to send them over the network.
+    pub fn active(&self) -> Vec<(Action, f32)> {
+        let mut active: Vec<(Action, f32)> = self
+            .values
+            .iter()
+            .filter(|(_, &value)| value > 0.0)
+            .map(|(&action, &value)| (action, value))
+            .collect();
+        active.sort_by_key(|&(action, _)| Action::ALL.iter().position(|&other| other == action));
+        active
+    }
+
+    // Moves on a frame with values that came from somewhere other than local input.
+    pub fn update(&mut self, values: &[(Action, f32)]) {
+        self.set(values.iter().copied().collect());
+    }
+
+    fn set(&mut self, values: HashMap<Action, f32>) {
+        self.previous = std::mem::replace(&mut self.values, values);
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct PlayerActions {
+    players: Vec<ActionState>,
+    // Returned for players without any bindings.
+    idle: ActionState,
+}
+
+impl PlayerActions {
+    pub fn get(&self, player: usize) -> &ActionState {
+        self.players.get(player).unwrap_or(&self.idle)
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    // Overrides a player's local input, must run after `ActionSystem` to stick for the frame.
+    pub fn set_player(&mut self, player: usize, action_state: &ActionState) {
+        if self.players.len() <= player {
+            self.players.resize_with(player + 1, ActionState::default);
+        }
+        self.players[player] = action_state.clone();
+    }
+}
+
+// Systems that change the raw input, like the console and replays, run before this.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActionSystem;
+
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .init_resource::<PlayerActions>()
+            .configure_sets(PreUpdate, ActionSystem.after(InputSystem))
+            .add_systems(PreUpdate, update_actions.in_set(ActionSystem))
+            .add_console_command("bindings", "list the input bindings", bindings_command)
+            .add_console_command(
+                "bind",
+                "bind [player] <action> <binding>: add a binding, e.g. 'bind 2 Fire Key(F)'",
+                bind_command,
+            )
+            .add_console_command(
+                "unbind",
+                "unbind [player] <action>: remove all bindings of an action",
+                unbind_command,
+            )
+            .add_console_command(
+                "controls",
+                "controls [Classic|MouseAim]: show or pick the control scheme",
+                controls_command,
+            );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_actions(
+    mut player_actions: ResMut<PlayerActions>,
+    settings: Res<Settings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    button_input: Res<Input<GamepadButton>>,
+    button_axes: Res<Axis<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    console: Res<ConsoleState>,
+    inspector: Res<InspectorState>,
+) {
+    // Clicks on the console or inspector are meant for them, not for firing.
+    let mouse_free = !console.open && !inspector.open;
+    // Pads are handed out to players in the order they were connected.
+    let mut pads: Vec<Gamepad> = gamepads.iter().collect();
+    pads.sort_by_key(|gamepad| gamepad.id);
+
+    let players = &mut player_actions.players;
+    players.resize_with(settings.bindings.len(), ActionState::default);
+    for (player, (bindings, action_state)) in settings.bindings.iter().zip(players).enumerate() {
+        let gamepad = pads.get(player).copied();
+        let mut values = HashMap::default();
+        for &(action, binding) in bindings.iter() {
+            let value = match (binding, gamepad) {
+                (Binding::Key(key), _) => f32::from(u8::from(keyboard_input.pressed(key))),
+                (Binding::Mouse(button), _) => {
+                    f32::from(u8::from(mouse_free && mouse_input.pressed(button)))
+                }
+                (Binding::Button(button_type), Some(gamepad)) => {
+                    let button = GamepadButton::new(gamepad, button_type);
+                    button_axes
+                        .get(button)
+                        .unwrap_or(f32::from(u8::from(button_input.pressed(button))))
+                }
+                (Binding::Axis { axis, positive }, Some(gamepad)) => {
+                    let value = axes
+                        .get(GamepadAxis::new(gamepad, axis))
+                        .unwrap_or_default();
+                    let value = if positive { value } else { -value };
+                    dead_zone(value, settings.stick_dead_zone)
+                }
+                (_, None) => 0.0,
+            };
+            let entry = values.entry(action).or_insert(0.0);
+            *entry = f32::max(*entry, value);
+        }
+        action_state.set(values);
+    }
+}
+
+// Rescales the part of `value` past the dead zone back to 0..1.
+fn dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value <= dead_zone {
+        return 0.0;
+    }
+    ((value - dead_zone) / (1.0 - dead_zone).max(f32::EPSILON)).min(1.0)
+}
+
+pub(crate) fn save_settings(world: &World) -> Result<String, String> {
+    let Some(SettingsFile(path)) = world.get_resource::<SettingsFile>() else {
+        return Ok("settings changed".into());
+    };
+    world
+        .resource::<Settings>()
+        .save(path)
+        .map_err(|err| err.to_string())?;
+    Ok(format!("settings saved to {path:?}"))
+}
+
+fn parse_action(text: &str) -> Result<Action, String> {
+    ron::from_str(text).map_err(|_| format!("unknown action '{text}'"))
+}
+
+// Commands take an optional player number, counting from 1, before their other arguments.
+fn split_player<'a>(args: &'a [&'a str]) -> (usize, &'a [&'a str]) {
+    match args.split_first() {
+        Some((first, rest)) => match first.parse::<usize>() {
+            Ok(player) if player > 0 => (player - 1, rest),
+            _ => (0, args),
+        },
+        None => (0, args),
+    }
+}
+
+fn bindings_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let settings = world.resource::<Settings>();
+    let mut lines = vec![];
+    for (player, bindings) in settings.bindings.iter().enumerate() {
+        lines.push(format!("player {}:", player + 1));
+        for action in Action::ALL {
+            let bound: Vec<String> = bindings
+                .iter()
+                .filter(|(bound, _)| *bound == action)
+                .map(|(_, binding)| ron::to_string(binding).unwrap_or_default())
+                .collect();
+            lines.push(format!("  {action:?}: {}", bound.join(", ")));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+fn bind_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let (player, args) = split_player(args);
+    let Some((action, binding)) = args.split_first().filter(|(_, rest)| !rest.is_empty()) else {
+        return Err("usage: bind [player] <action> <binding>".into());
+    };
+    let action = parse_action(action)?;
+    let binding = binding.join(" ");
+    let binding: Binding =
+        ron::from_str(&binding).map_err(|_| format!("'{binding}' is not a valid binding"))?;
+
+    let mut settings = world.resource_mut::<Settings>();
+    if settings.bindings.len() <= player {
+        settings.bindings.resize_with(player + 1, Vec::new);
+    }
+    let bindings = &mut settings.bindings[player];
+    if !bindings.contains(&(action, binding)) {
+        bindings.push((action, binding));
+    }
+    save_settings(world)
+}
+
+fn unbind_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let (player, args) = split_player(args);
+    let [action] = args else {
+        return Err("usage: unbind [player] <action>".into());
+    };
+    let action = parse_action(action)?;
+    if let Some(bindings) = world.resource_mut::<Settings>().bindings.get_mut(player) {
+        bindings.retain(|(bound, _)| *bound != action);
+    }
+    save_settings(world)
+}
+
+fn controls_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [scheme] = args else {
+        let scheme = world.resource::<Settings>().control_scheme;
+        return Ok(format!("{scheme:?}"));
+    };
+    let scheme: ControlScheme =
+        ron::from_str(scheme).map_err(|_| format!("unknown control scheme '{scheme}'"))?;
+    world.resource_mut::<Settings>().control_scheme = scheme;
+    save_settings(world)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn dead_zone_rescales_the_remaining_travel() {
+        assert_eq!(dead_zone(0.1, 0.2), 0.0);
+        assert_eq!(dead_zone(-0.8, 0.2), 0.0);
+        assert!((dead_zone(0.6, 0.2) - 0.5).abs() < 1e-6);
+        assert_eq!(dead_zone(1.0, 0.2), 1.0);
+    }
+
+    #[test]
+    fn just_pressed_only_on_the_first_frame() {
+        let mut state = ActionState::default();
+        for expected in [true, false] {
+            state.set(HashMap::from_iter([(Action::Fire, 1.0)]));
+            assert!(state.pressed(Action::Fire));
+            assert_eq!(state.just_pressed(Action::Fire), expected);
+        }
+        state.set(HashMap::default());
+        assert!(!state.pressed(Action::Fire));
+    }
+
+    #[test]
+    fn mouse_bindings_wait_while_the_inspector_is_open() {
+        let mut world = World::new();
+        world.init_resource::<Settings>();
+        world.init_resource::<PlayerActions>();
+        world.init_resource::<Input<KeyCode>>();
+        world.init_resource::<Gamepads>();
+        world.init_resource::<Input<GamepadButton>>();
+        world.init_resource::<Axis<GamepadButton>>();
+        world.init_resource::<Axis<GamepadAxis>>();
+        world.init_resource::<ConsoleState>();
+        world.insert_resource(InspectorState {
+            open: true,
+            selected: None,
+        });
+        let mut mouse_input = Input::<MouseButton>::default();
+        mouse_input.press(MouseButton::Left);
+        world.insert_resource(mouse_input);
+
+        world.run_system_once(update_actions);
+        assert!(!world
+            .resource::<PlayerActions>()
+            .get(0)
+            .pressed(Action::Fire));
+
+        world.resource_mut::<InspectorState>().open = false;
+        world.run_system_once(update_actions);
+        assert!(world
+            .resource::<PlayerActions>()
+            .get(0)
+            .pressed(Action::Fire));
+    }
+
+    #[test]
+    fn bind_and_unbind_from_the_console() {
+        let mut world = World::new();
+        world.init_resource::<Settings>();
+        bind_command(&mut world, &["Fire", "Key(F)"]).unwrap();
+        bind_command(
+            &mut world,
+            &["3", "Fire", "Axis(axis:", "RightZ,", "positive:", "true)"],
+        )
+        .unwrap();
+        assert!(bind_command(&mut world, &["Jump", "Key(F)"]).is_err());
+        assert!(bind_command(&mut world, &["Fire", "Key(Nope)"]).is_err());
+
+        let bindings = &world.resource::<Settings>().bindings;
+        assert!(bindings[0].contains(&(Action::Fire, Binding::Key(KeyCode::F))));
+        assert_eq!(
+            bindings[2],
+            vec![(
+                Action::Fire,
+                Binding::Axis {
+                    axis: GamepadAxisType::RightZ,
+                    positive: true
+                }
+            )]
+        );
+
+        unbind_command(&mut world, &["1", "Fire"]).unwrap();
+        let bindings = &world.resource::<Settings>().bindings;
+        assert!(bindings[0]
+            .iter()
+            .all(|(action, _)| *action != Action::Fire));
+        assert!(bindings[1]
+            .iter()
+            .any(|(action, _)| *action == Action::Fire));
+    }
+
+    #[test]
+    fn pick_the_control_scheme_from_the_console() {
+        let mut world = World::new();
+        world.init_resource::<Settings>();
+        controls_command(&mut world, &["MouseAim"]).unwrap();
+        assert_eq!(
+            world.resource::<Settings>().control_scheme,
+            ControlScheme::MouseAim
+        );
+        assert_eq!(controls_command(&mut world, &[]).unwrap(), "MouseAim");
+        assert!(controls_command(&mut world, &["Joystick"]).is_err());
+    }
+}
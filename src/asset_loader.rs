@@ -1,25 +1,368 @@
-use bevy::prelude::*;
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetPath, LoadState, RecursiveDependencyLoadState},
+    gltf::Gltf,
+    prelude::*,
+};
+
+use crate::{
+    asteroids::ASTEROID_SCENE,
+    manifest::{AssetManifest, ManifestLoader, MANIFEST_PATH},
+    spaceship::{MISSILE_SCENE, SPACESHIP_SCENE},
+    state::{GameState, StartState},
+};
+
+const FONT_SIZE: f32 = 28.0;
+const ERROR_COLOR: Color = Color::rgb(1.0, 0.4, 0.4);
+const REQUIRED_SCENES: [&str; 3] = [SPACESHIP_SCENE, MISSILE_SCENE, ASTEROID_SCENE];
+
+// Handles for everything listed in the asset manifest, looked up by logical name.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct GameAssets {
+    scenes: HashMap<String, Vec<Handle<Scene>>>,
+    sounds: HashMap<String, Handle<AudioSource>>,
+    textures: HashMap<String, Handle<Image>>,
+}
+
+impl GameAssets {
+    pub fn scene(&self, name: &str) -> Handle<Scene> {
+        self.scene_variant(name, 0)
+    }
+
+    pub fn scene_variants(&self, name: &str) -> usize {
+        self.scenes.get(name).map_or(0, Vec::len)
+    }
+
+    pub fn scene_variant(&self, name: &str, variant: usize) -> Handle<Scene> {
+        match self.scenes.get(name) {
+            Some(variants) if !variants.is_empty() => variants[variant % variants.len()].clone(),
+            _ => Handle::default(),
+        }
+    }
+
+    pub fn sound(&self, name: &str) -> Handle<AudioSource> {
+        self.sounds.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn texture(&self, name: &str) -> Handle<Image> {
+        self.textures.get(name).cloned().unwrap_or_default()
+    }
+}
+
+// The whole file is tracked next to the asset so a missing or unreadable file shows up
+// as a failure rather than a scene label that never finishes loading.
+#[derive(Debug)]
+struct PendingAsset {
+    path: String,
+    file: UntypedHandle,
+    asset: UntypedHandle,
+}
+
+#[derive(Resource, Debug, Default)]
+struct PendingAssets {
+    manifest: Handle<AssetManifest>,
+    requested: bool,
+    assets: Vec<PendingAsset>,
+}
 
 #[derive(Resource, Debug, Default)]
-pub struct SceneAssets {
-    pub asteroid: Handle<Scene>,
-    pub spaceship: Handle<Scene>,
-    pub missiles: Handle<Scene>,
+pub struct AssetLoadErrors {
+    pub messages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AssetStatus {
+    Loading,
+    Loaded,
+    Failed(&'static str),
 }
 
+#[derive(Component, Debug)]
+struct LoadingScreen;
+
+#[derive(Component, Debug)]
+struct LoadingText;
+
 pub struct AssetLoaderPlugin;
 
 impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SceneAssets>()
-            .add_systems(Startup, load_assets);
+        app.init_asset::<AssetManifest>()
+            .init_asset_loader::<ManifestLoader>()
+            .init_resource::<GameAssets>()
+            .init_resource::<PendingAssets>()
+            .init_resource::<AssetLoadErrors>()
+            .register_type::<GameAssets>()
+            .add_systems(Startup, load_manifest)
+            .add_systems(OnEnter(GameState::Loading), spawn_loading_screen)
+            .add_systems(
+                Update,
+                (request_assets, check_assets)
+                    .chain()
+                    .run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(OnExit(GameState::Loading), despawn_loading_screen)
+            .add_systems(OnEnter(GameState::LoadingFailed), spawn_error_screen);
     }
 }
 
-fn load_assets(mut scene_assets: ResMut<SceneAssets>, asset_server: Res<AssetServer>) {
-    *scene_assets = SceneAssets {
-        asteroid: asset_server.load("Asteroid.glb#Scene0"),
-        spaceship: asset_server.load("Spaceship.glb#Scene0"),
-        missiles: asset_server.load("Missiles.glb#Scene0"),
+fn load_manifest(mut pending: ResMut<PendingAssets>, asset_server: Res<AssetServer>) {
+    pending.manifest = asset_server.load(MANIFEST_PATH);
+}
+
+fn request_assets(
+    mut pending: ResMut<PendingAssets>,
+    mut game_assets: ResMut<GameAssets>,
+    mut errors: ResMut<AssetLoadErrors>,
+    manifests: Res<Assets<AssetManifest>>,
+    asset_server: Res<AssetServer>,
+) {
+    if pending.requested {
+        return;
+    }
+    if asset_server.get_load_state(&pending.manifest) == Some(LoadState::Failed) {
+        errors
+            .messages
+            .push(format!("{MANIFEST_PATH}: missing or malformed file"));
+        return;
+    }
+    let Some(manifest) = manifests.get(&pending.manifest) else {
+        return;
+    };
+    pending.requested = true;
+
+    for name in REQUIRED_SCENES {
+        if manifest.scenes.contains_key(name) {
+            continue;
+        }
+        errors
+            .messages
+            .push(format!("{MANIFEST_PATH}: no scene named '{name}'"));
+    }
+
+    let mut assets = Vec::new();
+    for (name, paths) in manifest.scenes.iter() {
+        let variants = paths
+            .iter()
+            .map(|path| {
+                // Scenes come out of glTF files, the first one is used if no label is given.
+                let path = AssetPath::parse(path).into_owned();
+                let path = if path.label().is_none() {
+                    path.with_label("Scene0")
+                } else {
+                    path
+                };
+                let scene: Handle<Scene> = asset_server.load(path.clone_owned());
+                let file: Handle<Gltf> = asset_server.load(path.path().to_path_buf());
+                assets.push(PendingAsset {
+                    path: path.to_string(),
+                    file: file.untyped(),
+                    asset: scene.clone().untyped(),
+                });
+                scene
+            })
+            .collect();
+        game_assets.scenes.insert(name.clone(), variants);
+    }
+    for (name, path) in manifest.sounds.iter() {
+        let sound: Handle<AudioSource> = asset_server.load(path.clone());
+        assets.push(PendingAsset {
+            path: path.clone(),
+            file: sound.clone().untyped(),
+            asset: sound.clone().untyped(),
+        });
+        game_assets.sounds.insert(name.clone(), sound);
+    }
+    for (name, path) in manifest.textures.iter() {
+        let texture: Handle<Image> = asset_server.load(path.clone());
+        assets.push(PendingAsset {
+            path: path.clone(),
+            file: texture.clone().untyped(),
+            asset: texture.clone().untyped(),
+        });
+        game_assets.textures.insert(name.clone(), texture);
+    }
+    pending.assets = assets;
+}
+
+fn asset_status(
+    file: Option<LoadState>,
+    asset: Option<LoadState>,
+    dependencies: Option<RecursiveDependencyLoadState>,
+) -> AssetStatus {
+    match file {
+        Some(LoadState::Failed) => AssetStatus::Failed("missing or malformed file"),
+        // Labelled assets are finished before their file, so a loaded file without a
+        // loaded asset means the label isn't in it.
+        Some(LoadState::Loaded) if asset != Some(LoadState::Loaded) => {
+            AssetStatus::Failed("label not found in file")
+        }
+        Some(LoadState::Loaded) => match dependencies {
+            Some(RecursiveDependencyLoadState::Loaded) => AssetStatus::Loaded,
+            Some(RecursiveDependencyLoadState::Failed) => {
+                AssetStatus::Failed("a dependency failed to load")
+            }
+            _ => AssetStatus::Loading,
+        },
+        _ => AssetStatus::Loading,
+    }
+}
+
+fn check_assets(
+    pending: Res<PendingAssets>,
+    asset_server: Res<AssetServer>,
+    mut errors: ResMut<AssetLoadErrors>,
+    mut next_state: ResMut<NextState<GameState>>,
+    start_state: Res<StartState>,
+    mut query: Query<&mut Text, With<LoadingText>>,
+) {
+    let mut loaded = 0;
+    for asset in pending.assets.iter() {
+        let status = asset_status(
+            asset_server.get_load_state(asset.file.id()),
+            asset_server.get_load_state(asset.asset.id()),
+            asset_server.get_recursive_dependency_load_state(asset.asset.id()),
+        );
+        match status {
+            AssetStatus::Loaded => loaded += 1,
+            AssetStatus::Failed(reason) => {
+                errors.messages.push(format!("{}: {}", asset.path, reason))
+            }
+            AssetStatus::Loading => {}
+        }
+    }
+
+    if !errors.messages.is_empty() {
+        errors.messages.sort();
+        for message in errors.messages.iter() {
+            error!("Failed to load asset {}", message);
+        }
+        next_state.set(GameState::LoadingFailed);
+        return;
+    }
+
+    let total = pending.assets.len();
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value = if pending.requested {
+            format!("Loading assets... {loaded}/{total}")
+        } else {
+            "Loading asset manifest...".to_string()
+        };
+    }
+    if pending.requested && loaded == total {
+        next_state.set(start_state.0);
+    }
+}
+
+fn full_screen_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((full_screen_node(), LoadingScreen))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Loading assets...",
+                    TextStyle {
+                        font_size: FONT_SIZE,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                LoadingText,
+            ));
+        });
+}
+
+fn despawn_loading_screen(mut commands: Commands, query: Query<Entity, With<LoadingScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_error_screen(mut commands: Commands, errors: Res<AssetLoadErrors>) {
+    let style = TextStyle {
+        font_size: FONT_SIZE,
+        color: ERROR_COLOR,
+        ..default()
+    };
+    commands.spawn(full_screen_node()).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Failed to load game assets",
+            style.clone(),
+        ));
+        for message in errors.messages.iter() {
+            parent.spawn(TextBundle::from_section(
+                message.clone(),
+                TextStyle {
+                    font_size: FONT_SIZE * 0.7,
+                    ..style.clone()
+                },
+            ));
+        }
+        parent.spawn(TextBundle::from_section(
+            "Check the assets folder and restart the game.",
+            TextStyle {
+                font_size: FONT_SIZE * 0.7,
+                color: Color::GRAY,
+                ..default()
+            },
+        ));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_until_scene_and_dependencies_are_loaded() {
+        assert_eq!(
+            asset_status(Some(LoadState::Loading), None, None),
+            AssetStatus::Loading
+        );
+        assert_eq!(
+            asset_status(
+                Some(LoadState::Loaded),
+                Some(LoadState::Loaded),
+                Some(RecursiveDependencyLoadState::Loading)
+            ),
+            AssetStatus::Loading
+        );
+        assert_eq!(
+            asset_status(
+                Some(LoadState::Loaded),
+                Some(LoadState::Loaded),
+                Some(RecursiveDependencyLoadState::Loaded)
+            ),
+            AssetStatus::Loaded
+        );
+    }
+
+    #[test]
+    fn reports_missing_files_and_scenes() {
+        assert!(matches!(
+            asset_status(Some(LoadState::Failed), Some(LoadState::Loading), None),
+            AssetStatus::Failed(_)
+        ));
+        assert!(matches!(
+            asset_status(Some(LoadState::Loaded), Some(LoadState::Loading), None),
+            AssetStatus::Failed(_)
+        ));
     }
 }
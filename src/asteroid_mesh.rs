@@ -0,0 +1,144 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+    },
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// How far a vertex may be pushed in or out, as a fraction of the radius.
+pub const LUMPINESS: f32 = 0.3;
+const LUMPS: usize = 12;
+// Only the part of the sphere within this dot product of a lump's direction is raised.
+const LUMP_WIDTH: f32 = 0.4;
+
+pub struct AsteroidMesh {
+    pub mesh: Mesh,
+    // Mean distance of the surface from the centre, used for the collider.
+    pub radius: f32,
+}
+
+// Builds a lumpy rock by pushing the vertices of an icosphere in and out along a handful of
+// random directions. The same seed always gives the same mesh.
+pub fn generate(seed: u64, radius: f32, subdivisions: usize) -> AsteroidMesh {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let sphere = Mesh::try_from(shape::Icosphere {
+        radius: 1.0,
+        subdivisions,
+    })
+    .expect("icosphere subdivisions are small enough");
+
+    let Some(VertexAttributeValues::Float32x3(directions)) =
+        sphere.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        unreachable!("icospheres always have positions");
+    };
+    let indices: Vec<usize> = sphere
+        .indices()
+        .map(|indices| indices.iter().collect())
+        .unwrap_or_default();
+
+    let lumps: Vec<(Vec3, f32)> = (0..LUMPS)
+        .map(|_| {
+            let direction = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize_or_zero();
+            (direction, rng.gen_range(-LUMPINESS..LUMPINESS))
+        })
+        .collect();
+
+    let surface: Vec<Vec3> = directions
+        .iter()
+        .map(|&direction| {
+            let direction = Vec3::from(direction);
+            let offset: f32 = lumps
+                .iter()
+                .map(|&(lump, height)| {
+                    let falloff =
+                        ((direction.dot(lump) - (1.0 - LUMP_WIDTH)) / LUMP_WIDTH).max(0.0);
+                    height * falloff * falloff
+                })
+                .sum();
+            direction * radius * (1.0 + offset.clamp(-LUMPINESS, LUMPINESS))
+        })
+        .collect();
+    let mean_radius =
+        surface.iter().map(|vertex| vertex.length()).sum::<f32>() / surface.len() as f32;
+
+    // Every triangle gets its own vertices so the flat normals give a faceted look.
+    let mut positions = Vec::with_capacity(indices.len());
+    let mut normals = Vec::with_capacity(indices.len());
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [
+            surface[triangle[0]],
+            surface[triangle[1]],
+            surface[triangle[2]],
+        ];
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        positions.extend([a, b, c].map(|vertex| vertex.to_array()));
+        normals.extend([normal.to_array(); 3]);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32((0..indices.len() as u32).collect())));
+    AsteroidMesh {
+        mesh,
+        radius: mean_radius,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(mesh: &Mesh) -> Vec<Vec3> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("mesh has no positions");
+        };
+        positions
+            .iter()
+            .map(|&position| Vec3::from(position))
+            .collect()
+    }
+
+    #[test]
+    fn vertex_counts_follow_subdivisions() {
+        for subdivisions in 0..4 {
+            let asteroid = generate(1, 2.0, subdivisions);
+            // Each face of the icosahedron is split into (n + 1)^2 triangles.
+            let triangles = 20 * (subdivisions + 1).pow(2);
+            assert_eq!(asteroid.mesh.count_vertices(), triangles * 3);
+            assert_eq!(asteroid.mesh.indices().unwrap().len(), triangles * 3);
+        }
+    }
+
+    #[test]
+    fn vertices_stay_within_lumpiness_bounds() {
+        let radius = 3.0;
+        let asteroid = generate(42, radius, 2);
+        for vertex in positions(&asteroid.mesh) {
+            let distance = vertex.length();
+            assert!(distance >= radius * (1.0 - LUMPINESS) - 1e-4, "{distance}");
+            assert!(distance <= radius * (1.0 + LUMPINESS) + 1e-4, "{distance}");
+        }
+        assert!((asteroid.radius - radius).abs() <= radius * LUMPINESS);
+    }
+
+    #[test]
+    fn seed_decides_the_shape() {
+        let a = positions(&generate(7, 1.0, 2).mesh);
+        let b = positions(&generate(7, 1.0, 2).mesh);
+        let c = positions(&generate(8, 1.0, 2).mesh);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
@@ -1,82 +1,233 @@
 use bevy::prelude::*;
 use rand::Rng;
-use std::{collections::HashSet, ops::Range};
+use std::{ops::Range, time::Duration};
 
 use crate::{
-    asset_loader::SceneAssets,
-    collision_detection::Collider,
-    despawn,
-    movement::{Acceleration, MovingObejectBundle, Velocity},
+    asset_loader::GameAssets,
+    asteroid_mesh,
+    collision_detection::{Collider, CollisionDamage},
+    config::GameConfig,
+    console::ConsoleAppExt,
+    health::Health,
+    movement::{Acceleration, Velocity},
+    pool::{PoolCommandsExt, SpawnPooled},
+    rng::GameRng,
+    state::GameState,
+    wave::Wave,
 };
 
+pub const ASTEROID_SCENE: &str = "asteroid";
 const VELOCITY_SCALER: f32 = 5.0;
 const ACCELERATION_SCALER: f32 = 1.0;
-const SPAWN_RANGE_X: Range<f32> = -25.0..25.0;
-const SPAWN_RANGE_Z: Range<f32> = 0.0..25.0;
+pub const SPAWN_RANGE_X: Range<f32> = -25.0..25.0;
+pub const SPAWN_RANGE_Z: Range<f32> = 0.0..25.0;
 const SPAWN_TIMER_SECONDS: f32 = 1.0;
 const ROTATE_SPEED: f32 = 2.5;
 const RADIUS: f32 = 2.5;
+// Anything that hits an asteroid breaks it.
+const HEALTH: f32 = 1.0;
+const COLLISION_DAMAGE: f32 = 35.0;
+// Sizes the procedural meshes are made at, with the icosphere detail used for each.
+const PROCEDURAL_SIZES: [(f32, usize); 3] = [(0.6, 1), (1.0, 2), (1.6, 3)];
+const PROCEDURAL_VARIANTS: u64 = 4;
+const ROCK_COLOR: Color = Color::rgb(0.42, 0.38, 0.34);
 
-#[derive(Component, Debug)]
-pub struct Asteroid;
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct Asteroid {
+    // Scales the model and collider radius.
+    pub size: f32,
+    // Which of the manifest's asteroid scenes, or of the procedural meshes, is the model.
+    pub variant: usize,
+    pub procedural: bool,
+}
+
+impl Default for Asteroid {
+    fn default() -> Self {
+        Self {
+            size: 1.0,
+            variant: 0,
+            procedural: false,
+        }
+    }
+}
 
-#[derive(Resource, Debug)]
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource, Default)]
 pub struct SpawnTimer {
     timer: Timer,
 }
 
+impl Default for SpawnTimer {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(SPAWN_TIMER_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ProceduralAsteroid {
+    pub scene: Handle<Scene>,
+    pub size: f32,
+    pub radius: f32,
+}
+
+// Generated from the run's seed at startup and used instead of the manifest models when
+// `procedural_asteroids` is set in the config.
+#[derive(Resource, Debug, Default)]
+pub struct ProceduralAsteroids {
+    pub variants: Vec<ProceduralAsteroid>,
+}
+
 pub struct AsteroidPlugin;
 
 impl Plugin for AsteroidPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SpawnTimer {
-            timer: Timer::from_seconds(SPAWN_TIMER_SECONDS, TimerMode::Repeating),
-        })
-        .add_systems(
-            Update,
-            (spawn_asteroid, rotate_asteroids, handle_asteroid_collisions),
-        );
+        app.register_type::<Asteroid>()
+            .register_type::<SpawnTimer>()
+            .init_resource::<SpawnTimer>()
+            .init_resource::<ProceduralAsteroids>()
+            .add_systems(Startup, generate_procedural_asteroids)
+            .add_console_command(
+                "spawn asteroid",
+                "spawn asteroid <x> <z>: spawn an asteroid at a position",
+                spawn_asteroid_command,
+            )
+            .add_systems(
+                Update,
+                (spawn_asteroid, rotate_asteroids).run_if(in_state(GameState::InGame)),
+            );
     }
 }
 
+fn generate_procedural_asteroids(
+    mut procedural: ResMut<ProceduralAsteroids>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut scenes: ResMut<Assets<Scene>>,
+    rng: Res<GameRng>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: ROCK_COLOR,
+        perceptual_roughness: 0.95,
+        ..default()
+    });
+    let mut seed = rng.seed;
+    for (size, subdivisions) in PROCEDURAL_SIZES {
+        for _ in 0..PROCEDURAL_VARIANTS {
+            seed = seed.wrapping_add(1);
+            let asteroid = asteroid_mesh::generate(seed, RADIUS * size, subdivisions);
+            let mut world = World::new();
+            world.spawn(PbrBundle {
+                mesh: meshes.add(asteroid.mesh),
+                material: material.clone(),
+                ..default()
+            });
+            procedural.variants.push(ProceduralAsteroid {
+                scene: scenes.add(Scene::new(world)),
+                size,
+                radius: asteroid.radius,
+            });
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_asteroid(
     mut commands: Commands,
     mut spawn_timer: ResMut<SpawnTimer>,
     time: Res<Time>,
-    scene_assets: Res<SceneAssets>,
+    game_assets: Res<GameAssets>,
+    procedural: Res<ProceduralAsteroids>,
+    config: Res<GameConfig>,
+    wave: Res<Wave>,
+    mut rng: ResMut<GameRng>,
 ) {
+    // The sector world streams its own asteroids in, see `sector`.
+    if config.sector_world {
+        return;
+    }
+    let seconds = config.asteroid_spawn_seconds / wave.spawn_rate();
+    spawn_timer
+        .timer
+        .set_duration(Duration::from_secs_f32(seconds));
     spawn_timer.timer.tick(time.delta());
     if !spawn_timer.timer.just_finished() {
         return;
     }
 
-    let mut rng = rand::thread_rng();
-
     let translation = Vec3::new(
         rng.gen_range(SPAWN_RANGE_X),
         0.0,
         rng.gen_range(SPAWN_RANGE_Z),
     );
+    let procedural = config.procedural_asteroids.then_some(&*procedural);
+    commands.spawn_pooled(asteroid_spawn(
+        &game_assets,
+        procedural,
+        translation,
+        &mut rng,
+        true,
+    ));
+}
 
+// Sector asteroids stay around for much longer than the arena's, so they only drift instead of
+// speeding up.
+pub(crate) fn asteroid_spawn(
+    game_assets: &GameAssets,
+    procedural: Option<&ProceduralAsteroids>,
+    translation: Vec3,
+    rng: &mut GameRng,
+    accelerating: bool,
+) -> SpawnPooled<impl Bundle> {
     let mut random_unit_vector =
         || Vec3::new(rng.gen_range(-1.0..1.0), 0.0, rng.gen_range(-1.0..1.0)).normalize_or_zero();
 
     let velocity = random_unit_vector() * VELOCITY_SCALER;
     let acceleration = random_unit_vector() * ACCELERATION_SCALER;
+    let acceleration = if accelerating {
+        acceleration
+    } else {
+        Vec3::ZERO
+    };
+    let (asteroid, scene, scale, radius) = match procedural {
+        Some(procedural) if !procedural.variants.is_empty() => {
+            let variant = rng.gen_range(0..procedural.variants.len());
+            let model = &procedural.variants[variant];
+            let asteroid = Asteroid {
+                size: model.size,
+                variant,
+                procedural: true,
+            };
+            (asteroid, model.scene.clone(), 1.0, model.radius)
+        }
+        // The manifest models all come in one size, only the procedural meshes vary.
+        _ => {
+            let size = 1.0;
+            let variant = rng.gen_range(0..game_assets.scene_variants(ASTEROID_SCENE).max(1));
+            let asteroid = Asteroid {
+                size,
+                variant,
+                procedural: false,
+            };
+            let scene = game_assets.scene_variant(ASTEROID_SCENE, variant);
+            (asteroid, scene, size, RADIUS * size)
+        }
+    };
 
-    commands.spawn((
-        MovingObejectBundle {
-            velocity: Velocity::new(velocity),
-            acceleration: Acceleration::new(acceleration),
-            collider: Collider::new(RADIUS),
-            model: SceneBundle {
-                scene: scene_assets.asteroid.clone(),
-                transform: Transform::from_translation(translation),
-                ..default()
-            },
-        },
-        Asteroid,
-    ));
+    SpawnPooled {
+        scene,
+        transform: Transform::from_translation(translation).with_scale(Vec3::splat(scale)),
+        bundle: (
+            Velocity::new(velocity),
+            Acceleration::new(acceleration),
+            Collider::new(radius),
+            Health::new(HEALTH),
+            asteroid,
+            CollisionDamage::new(COLLISION_DAMAGE),
+        ),
+    }
 }
 
 fn rotate_asteroids(mut query: Query<&mut Transform, With<Asteroid>>, time: Res<Time>) {
@@ -85,23 +236,26 @@ fn rotate_asteroids(mut query: Query<&mut Transform, With<Asteroid>>, time: Res<
     }
 }
 
-fn handle_asteroid_collisions(
-    mut commands: Commands,
-    query: Query<(Entity, &Collider), With<Asteroid>>,
-) {
-    let mut despawned_entity = HashSet::new();
-    for (entity, collider) in query.iter() {
-        if despawned_entity.contains(&entity) {
-            continue;
-        }
-        for &collided_entity in collider.colliding_entities.iter() {
-            // Asteroid collided with another asteroid.
-            if query.get(collided_entity).is_ok() {
-                continue;
-            }
-            commands.entity(entity).despawn_recursive();
-            despawned_entity.insert(entity);
-            break;
-        }
-    }
+fn spawn_asteroid_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [x, z] = args else {
+        return Err("usage: spawn asteroid <x> <z>".into());
+    };
+    let x: f32 = x.parse().map_err(|_| format!("'{x}' is not a number"))?;
+    let z: f32 = z.parse().map_err(|_| format!("'{z}' is not a number"))?;
+
+    let spawn = world.resource_scope(|world, mut rng: Mut<GameRng>| {
+        let procedural = world
+            .resource::<GameConfig>()
+            .procedural_asteroids
+            .then(|| world.resource::<ProceduralAsteroids>());
+        asteroid_spawn(
+            world.resource::<GameAssets>(),
+            procedural,
+            Vec3::new(x, 0.0, z),
+            &mut rng,
+            true,
+        )
+    });
+    let entity = spawn.spawn(world);
+    Ok(format!("spawned asteroid {entity:?}"))
 }
@@ -0,0 +1,44 @@
+use std::{fs::File, io::BufReader, path::PathBuf, process};
+
+use bevy_game::telemetry::{summarize, TelemetryRecord, TelemetryReport};
+use clap::Parser;
+
+/// Print accuracy, time-to-kill and survival statistics from a telemetry file.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// JSON-lines file written with `bevy_game --telemetry <FILE>`.
+    file: PathBuf,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let records = File::open(&cli.file)
+        .and_then(|file| TelemetryRecord::read_all(BufReader::new(file)))
+        .unwrap_or_else(|err| {
+            eprintln!("error: could not read {:?}: {err}", cli.file);
+            process::exit(1);
+        });
+    let report = TelemetryReport::from_records(&records);
+
+    println!("records:             {}", records.len());
+    println!("runs:                {}", report.runs);
+    println!("shots fired:         {}", report.shots);
+    println!("missiles that hit:   {}", report.hits);
+    match report.accuracy() {
+        Some(accuracy) => println!("accuracy:            {:.1}%", accuracy * 100.0),
+        None => println!("accuracy:            n/a"),
+    }
+    println!("asteroids destroyed: {}", report.asteroids_destroyed);
+    print_stats("time to kill (s):", &report.times_to_kill);
+    print_stats("survival time (s):", &report.survival_times);
+}
+
+fn print_stats(label: &str, values: &[f32]) {
+    match summarize(values) {
+        Some((mean, min, max)) => {
+            println!("{label:<20} mean {mean:.2}, min {min:.2}, max {max:.2}")
+        }
+        None => println!("{label:<20} n/a"),
+    }
+}
@@ -0,0 +1,512 @@
+use std::f32::consts::TAU;
+
+use bevy::{ecs::system::CommandQueue, prelude::*};
+
+use crate::{
+    collision_detection::{apply_collision_damage, Collider, CollisionDamage, CollisionEvent},
+    config::GameConfig,
+    console::ConsoleAppExt,
+    despawn::PlayArea,
+    health::{DamageEvent, Health, Invulnerable},
+    movement::{Acceleration, Velocity},
+    pool::PoolCommandsExt,
+    spaceship::{Spaceship, SpaceshipMissile},
+    state::GameState,
+    steering,
+    ufo::{enemy_projectile, UfoAssets, PROJECTILE_RADIUS},
+    wave::Wave,
+};
+
+const SPAWN_TRANSLATION: Vec3 = Vec3::new(0.0, 0.0, 40.0);
+const HULL_RADIUS: f32 = 6.0;
+const HEALTH: f32 = 2000.0;
+const COLLISION_DAMAGE: f32 = 100.0;
+// Weak points stick out of the hull so missiles reach them before hitting the armour. Each is
+// (offset, radius, how much of the damage is passed on to the boss).
+const WEAK_POINTS: [(Vec3, f32, f32); 3] = [
+    (Vec3::new(-6.5, 0.0, -1.5), 1.8, 1.0),
+    (Vec3::new(6.5, 0.0, -1.5), 1.8, 1.0),
+    (Vec3::new(0.0, 0.0, -6.8), 1.4, 2.0),
+];
+// Between them the weak points can always soak up more than the boss's health.
+const WEAK_POINT_HEALTH: f32 = HEALTH / 2.0;
+// The boss moves on to the next phase below each of these fractions of its health.
+const PHASE_THRESHOLDS: [f32; 2] = [0.66, 0.33];
+const STRAFE_WAYPOINTS: [Vec3; 2] = [Vec3::new(-18.0, 0.0, 25.0), Vec3::new(18.0, 0.0, 25.0)];
+// Far enough out that shots clear the weak points as well as the hull.
+const MUZZLE_DISTANCE: f32 = 9.0 + PROJECTILE_RADIUS;
+const WAYPOINT_REACHED: f32 = 2.0;
+const MAX_SPEED: [f32; 3] = [6.0, 9.0, 5.0];
+const MAX_FORCE: f32 = 10.0;
+const ATTACK_SECONDS: [f32; 3] = [1.8, 2.2, 0.12];
+const AIMED_SHOTS: usize = 5;
+const AIMED_SPREAD: f32 = 0.5;
+const RADIAL_SHOTS: usize = 14;
+const SPIRAL_STEP: f32 = 0.35;
+const HULL_COLOR: Color = Color::rgb(0.25, 0.22, 0.3);
+const WEAK_POINT_COLOR: Color = Color::rgb(1.0, 0.7, 0.1);
+const BAR_WIDTH: f32 = 400.0;
+const BAR_HEIGHT: f32 = 14.0;
+const BAR_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const BAR_COLOR: Color = Color::rgb(0.85, 0.15, 0.2);
+const FONT_SIZE: f32 = 18.0;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct Boss {
+    pub phase: usize,
+    attack_timer: Timer,
+    attack_step: u32,
+    waypoint: usize,
+}
+
+impl Default for Boss {
+    fn default() -> Self {
+        Self {
+            phase: 0,
+            attack_timer: Timer::from_seconds(ATTACK_SECONDS[0], TimerMode::Repeating),
+            attack_step: 0,
+            waypoint: 0,
+        }
+    }
+}
+
+// On the boss and all of its weak points, so they never collide with each other.
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct BossPart;
+
+// Missile hits on a weak point also damage the boss it belongs to.
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct WeakPoint {
+    pub multiplier: f32,
+}
+
+impl Default for WeakPoint {
+    fn default() -> Self {
+        Self { multiplier: 1.0 }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct BossAssets {
+    hull_mesh: Handle<Mesh>,
+    hull_material: Handle<StandardMaterial>,
+    weak_point_mesh: Handle<Mesh>,
+    weak_point_material: Handle<StandardMaterial>,
+}
+
+#[derive(Component, Debug)]
+struct HealthBar;
+
+#[derive(Component, Debug)]
+struct HealthBarFill;
+
+#[derive(Component, Debug)]
+struct HealthBarText;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Boss>()
+            .register_type::<BossPart>()
+            .register_type::<WeakPoint>()
+            .init_resource::<BossAssets>()
+            .add_systems(Startup, (build_boss_assets, spawn_health_bar))
+            .add_systems(
+                Update,
+                (
+                    spawn_boss_on_wave,
+                    damage_weak_points.after(apply_collision_damage),
+                    update_boss_phase,
+                    move_boss,
+                    boss_attacks,
+                )
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Update, update_health_bar)
+            .add_console_command("spawn boss", "spawn the wave boss", spawn_boss_command);
+    }
+}
+
+// Which phase the boss is in with this fraction of its health left.
+fn phase_for(health_fraction: f32) -> usize {
+    PHASE_THRESHOLDS
+        .iter()
+        .filter(|&&threshold| health_fraction < threshold)
+        .count()
+}
+
+// Evenly spaced directions all the way around, turned by `offset`.
+fn radial_burst(count: usize, offset: f32) -> Vec<Vec3> {
+    (0..count)
+        .map(|index| {
+            let angle = offset + index as f32 * TAU / count as f32;
+            Vec3::new(angle.cos(), 0.0, angle.sin())
+        })
+        .collect()
+}
+
+// A fan of directions centred on `aim`, `spread` radians from edge to edge.
+fn aimed_spread(aim: Vec3, count: usize, spread: f32) -> Vec<Vec3> {
+    let aim = Vec3::new(aim.x, 0.0, aim.z).normalize_or_zero();
+    (0..count)
+        .map(|index| {
+            let t = if count > 1 {
+                index as f32 / (count - 1) as f32 - 0.5
+            } else {
+                0.0
+            };
+            Quat::from_rotation_y(t * spread) * aim
+        })
+        .collect()
+}
+
+fn build_boss_assets(
+    mut boss_assets: ResMut<BossAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    *boss_assets = BossAssets {
+        hull_mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: HULL_RADIUS,
+            ..default()
+        })),
+        hull_material: materials.add(StandardMaterial {
+            base_color: HULL_COLOR,
+            metallic: 0.6,
+            perceptual_roughness: 0.5,
+            ..default()
+        }),
+        weak_point_mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 1.0,
+            ..default()
+        })),
+        weak_point_material: materials.add(StandardMaterial {
+            base_color: WEAK_POINT_COLOR,
+            emissive: WEAK_POINT_COLOR,
+            ..default()
+        }),
+    };
+}
+
+fn spawn_boss(commands: &mut Commands, boss_assets: &BossAssets, center: Vec3) -> Entity {
+    commands
+        .spawn((
+            PbrBundle {
+                mesh: boss_assets.hull_mesh.clone(),
+                material: boss_assets.hull_material.clone(),
+                transform: Transform::from_translation(center + SPAWN_TRANSLATION)
+                    .with_scale(Vec3::new(1.0, 0.4, 1.0)),
+                ..default()
+            },
+            Velocity::new(Vec3::ZERO),
+            Acceleration::new(Vec3::ZERO),
+            Collider::new(HULL_RADIUS),
+            Health::new(HEALTH),
+            CollisionDamage::new(COLLISION_DAMAGE),
+            // The hull is armour, only the weak points can hurt the boss.
+            Invulnerable,
+            Boss::default(),
+            BossPart,
+        ))
+        .with_children(|parent| {
+            for (offset, radius, multiplier) in WEAK_POINTS {
+                parent.spawn((
+                    PbrBundle {
+                        mesh: boss_assets.weak_point_mesh.clone(),
+                        material: boss_assets.weak_point_material.clone(),
+                        // Undo the hull's squash so the weak points stay round.
+                        transform: Transform::from_translation(offset).with_scale(Vec3::new(
+                            radius,
+                            radius / 0.4,
+                            radius,
+                        )),
+                        ..default()
+                    },
+                    Collider::new(radius),
+                    Health::new(WEAK_POINT_HEALTH),
+                    CollisionDamage::new(COLLISION_DAMAGE),
+                    WeakPoint { multiplier },
+                    BossPart,
+                    Invulnerable,
+                ));
+            }
+        })
+        .id()
+}
+
+fn spawn_boss_on_wave(
+    mut commands: Commands,
+    wave: Res<Wave>,
+    config: Res<GameConfig>,
+    boss_assets: Res<BossAssets>,
+    play_area: Res<PlayArea>,
+    query: Query<(), With<Boss>>,
+    mut last_wave: Local<u32>,
+) {
+    if wave.number == *last_wave {
+        return;
+    }
+    *last_wave = wave.number;
+    let interval = config.boss_wave_interval;
+    if wave.number.checked_rem(interval) != Some(0) || !query.is_empty() {
+        return;
+    }
+    info!("Boss approaching on wave {}", wave.number);
+    spawn_boss(&mut commands, &boss_assets, play_area.center);
+}
+
+// Weak points are left out of the usual collision damage so only missiles can hurt them.
+fn damage_weak_points(
+    mut collision_event_reader: EventReader<CollisionEvent>,
+    mut damage_event_writer: EventWriter<DamageEvent>,
+    mut weak_point_query: Query<(&WeakPoint, &Parent, &mut Health), Without<Boss>>,
+    mut boss_query: Query<&mut Health, With<Boss>>,
+    missile_query: Query<&CollisionDamage, With<SpaceshipMissile>>,
+) {
+    for &CollisionEvent {
+        entity,
+        collided_entity,
+    } in collision_event_reader.read()
+    {
+        let Ok(damage) = missile_query.get(collided_entity) else {
+            continue;
+        };
+        let Ok((weak_point, parent, mut health)) = weak_point_query.get_mut(entity) else {
+            continue;
+        };
+        health.value -= damage.amount;
+        damage_event_writer.send(DamageEvent {
+            entity,
+            source: collided_entity,
+            amount: damage.amount,
+            health_left: health.value,
+        });
+
+        let Ok(mut boss_health) = boss_query.get_mut(parent.get()) else {
+            continue;
+        };
+        let amount = damage.amount * weak_point.multiplier;
+        boss_health.value -= amount;
+        damage_event_writer.send(DamageEvent {
+            entity: parent.get(),
+            source: collided_entity,
+            amount,
+            health_left: boss_health.value,
+        });
+    }
+}
+
+fn update_boss_phase(mut query: Query<(&mut Boss, &Health)>) {
+    for (mut boss, health) in query.iter_mut() {
+        let phase = phase_for(health.value / HEALTH);
+        if phase > boss.phase {
+            info!("Boss entering phase {}", phase + 1);
+            boss.phase = phase;
+            boss.attack_step = 0;
+            boss.attack_timer = Timer::from_seconds(ATTACK_SECONDS[phase], TimerMode::Repeating);
+        }
+    }
+}
+
+fn move_boss(
+    mut boss_query: Query<(&mut Boss, &Transform, &mut Velocity, &mut Acceleration)>,
+    spaceship_query: Query<&Transform, (With<Spaceship>, Without<Boss>)>,
+    play_area: Res<PlayArea>,
+) {
+    for (mut boss, transform, mut velocity, mut acceleration) in boss_query.iter_mut() {
+        let position = transform.translation;
+        let target = steering::nearest(
+            position,
+            spaceship_query
+                .iter()
+                .map(|transform| (transform.translation, ())),
+        )
+        .map(|(target, _)| target);
+        let max_speed = MAX_SPEED[boss.phase];
+        // The last phase goes after the spaceship, the others strafe across the field.
+        let destination = match target {
+            Some(target) if boss.phase == PHASE_THRESHOLDS.len() => target,
+            _ => {
+                let waypoint = play_area.center + STRAFE_WAYPOINTS[boss.waypoint];
+                if position.distance(waypoint) < WAYPOINT_REACHED {
+                    boss.waypoint = (boss.waypoint + 1) % STRAFE_WAYPOINTS.len();
+                }
+                play_area.center + STRAFE_WAYPOINTS[boss.waypoint]
+            }
+        };
+        acceleration.value = steering::seek(position, velocity.value, destination, max_speed)
+            .clamp_length_max(MAX_FORCE);
+        velocity.value = velocity.value.clamp_length_max(max_speed);
+    }
+}
+
+fn boss_attacks(
+    mut commands: Commands,
+    mut boss_query: Query<(&mut Boss, &GlobalTransform)>,
+    spaceship_query: Query<&Transform, With<Spaceship>>,
+    ufo_assets: Res<UfoAssets>,
+    time: Res<Time>,
+) {
+    for (mut boss, transform) in boss_query.iter_mut() {
+        boss.attack_timer.tick(time.delta());
+        if !boss.attack_timer.just_finished() {
+            continue;
+        }
+        let position = transform.translation();
+        let target = steering::nearest(
+            position,
+            spaceship_query
+                .iter()
+                .map(|transform| (transform.translation, ())),
+        )
+        .map(|(target, _)| target);
+        let step = boss.attack_step as f32;
+        boss.attack_step += 1;
+
+        let directions = match boss.phase {
+            0 => match target {
+                Some(target) => aimed_spread(target - position, AIMED_SHOTS, AIMED_SPREAD),
+                None => continue,
+            },
+            1 => radial_burst(RADIAL_SHOTS, step * TAU / RADIAL_SHOTS as f32 / 2.0),
+            _ => radial_burst(2, step * SPIRAL_STEP),
+        };
+        for direction in directions {
+            let translation = position + direction * MUZZLE_DISTANCE;
+            commands.spawn_pooled(enemy_projectile(&ufo_assets, translation, direction));
+        }
+    }
+}
+
+fn spawn_health_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(8.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HealthBar,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: FONT_SIZE,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                HealthBarText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(BAR_HEIGHT),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BAR_COLOR.into(),
+                            ..default()
+                        },
+                        HealthBarFill,
+                    ));
+                });
+        });
+}
+
+fn update_health_bar(
+    boss_query: Query<(&Boss, &Health)>,
+    mut bar_query: Query<&mut Visibility, With<HealthBar>>,
+    mut fill_query: Query<&mut Style, With<HealthBarFill>>,
+    mut text_query: Query<&mut Text, With<HealthBarText>>,
+) {
+    let boss = boss_query.get_single().ok();
+    for mut visibility in bar_query.iter_mut() {
+        *visibility = if boss.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    let Some((boss, health)) = boss else {
+        return;
+    };
+    for mut style in fill_query.iter_mut() {
+        style.width = Val::Percent((health.value / HEALTH).clamp(0.0, 1.0) * 100.0);
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("BOSS - phase {}", boss.phase + 1);
+    }
+}
+
+fn spawn_boss_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    if world.query::<&Boss>().iter(world).next().is_some() {
+        return Err("a boss is already here".into());
+    }
+    let center = world.resource::<PlayArea>().center;
+    world.resource_scope(|world, boss_assets: Mut<BossAssets>| {
+        let mut queue = CommandQueue::default();
+        let entity = spawn_boss(&mut Commands::new(&mut queue, world), &boss_assets, center);
+        queue.apply(world);
+        Ok(format!("spawned boss {entity:?}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_follow_health() {
+        assert_eq!(phase_for(1.0), 0);
+        assert_eq!(phase_for(0.7), 0);
+        assert_eq!(phase_for(0.5), 1);
+        assert_eq!(phase_for(0.1), 2);
+        assert_eq!(phase_for(-0.2), 2);
+    }
+
+    #[test]
+    fn radial_burst_covers_the_circle() {
+        let directions = radial_burst(8, 0.0);
+        assert_eq!(directions.len(), 8);
+        let sum: Vec3 = directions.iter().sum();
+        assert!(sum.length() < 1e-4);
+        assert!(directions.iter().all(|d| (d.length() - 1.0).abs() < 1e-4));
+    }
+
+    #[test]
+    fn aimed_spread_is_centred_on_the_aim() {
+        let aim = Vec3::new(0.0, 3.0, -10.0);
+        let directions = aimed_spread(aim, 5, 0.5);
+        assert!((directions[2] - Vec3::NEG_Z).length() < 1e-4);
+        let first = directions[0].angle_between(Vec3::NEG_Z);
+        let last = directions[4].angle_between(Vec3::NEG_Z);
+        assert!((first - 0.25).abs() < 1e-4 && (last - 0.25).abs() < 1e-4);
+        assert_eq!(aimed_spread(aim, 1, 0.5), vec![Vec3::NEG_Z]);
+    }
+}
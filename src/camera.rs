@@ -1,18 +1,304 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    action::save_settings,
+    collision_detection::{apply_collision_damage, Collider},
+    config::GameConfig,
+    console::ConsoleAppExt,
+    health::DamageEvent,
+    movement::Velocity,
+    settings::{CameraMode, Settings},
+    spaceship::Spaceship,
+};
 
 const CAMERA_DISTANCE: f32 = 80.0;
+// With several ships the camera backs off to keep them all in view.
+const FRAMING_SCALE: f32 = 1.4;
+const FRAMING_SPEED: f32 = 2.0;
+const FOLLOW_SPEED: f32 = 3.0;
+// How far ahead of the ship the follow camera looks, in seconds of its velocity.
+const LOOKAHEAD_SECONDS: f32 = 0.5;
+const ZOOM_PER_SPEED: f32 = 0.6;
+const MAX_ZOOM: f32 = 30.0;
+const TRAUMA_DECAY: f32 = 1.2;
+const SHIP_HIT_TRAUMA: f32 = 0.4;
+const EXPLOSION_TRAUMA_PER_RADIUS: f32 = 0.04;
+const MAX_SHAKE_OFFSET: f32 = 2.5;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+const SHAKE_FREQUENCY: f32 = 15.0;
+
+#[derive(Component, Debug)]
+pub struct GameCamera;
+
+// Where the camera eases towards, the shake is added on top of it.
+#[derive(Component, Debug)]
+pub struct CameraRig {
+    pub focus: Vec3,
+    pub distance: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::ZERO,
+            distance: CAMERA_DISTANCE,
+        }
+    }
+}
+
+// Builds up with hits and explosions and wears off over time, the camera shakes with its square.
+#[derive(Resource, Debug, Default)]
+pub struct Trauma {
+    pub value: f32,
+}
+
+impl Trauma {
+    pub fn add(&mut self, amount: f32) {
+        self.value = (self.value + amount).min(1.0);
+    }
+}
+
+// Where the mouse cursor points on the plane everything moves in, if it is over the window.
+#[derive(Resource, Debug, Default)]
+pub struct CursorPosition {
+    pub world: Option<Vec3>,
+}
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_camera);
+        app.init_resource::<CursorPosition>()
+            .init_resource::<Trauma>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(PreUpdate, update_cursor_position)
+            .add_systems(
+                Update,
+                (
+                    add_trauma.after(apply_collision_damage),
+                    update_camera_rig,
+                    place_camera,
+                )
+                    .chain(),
+            )
+            .add_console_command(
+                "camera",
+                "camera [Fixed|Follow]: show or pick the camera mode",
+                camera_command,
+            );
     }
 }
 
 fn spawn_camera(mut commands: Commands) {
-    commands.spawn(Camera3dBundle {
-        transform: Transform::from_xyz(0.0, CAMERA_DISTANCE, 0.0).looking_at(Vec3::ZERO, Vec3::Z),
-        ..default()
-    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, CAMERA_DISTANCE, 0.0)
+                .looking_at(Vec3::ZERO, Vec3::Z),
+            ..default()
+        },
+        GameCamera,
+        CameraRig::default(),
+    ));
+}
+
+pub(crate) fn update_cursor_position(
+    mut cursor_position: ResMut<CursorPosition>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+) {
+    cursor_position.world = window_query
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .zip(camera_query.get_single().ok())
+        .and_then(|(cursor, (camera, transform))| camera.viewport_to_world(transform, cursor))
+        .and_then(|ray| {
+            let distance = ray.intersect_plane(Vec3::ZERO, Vec3::Y)?;
+            Some(ray.get_point(distance))
+        });
+}
+
+// The ship being hit and anything blowing up, bigger things shaking harder.
+fn add_trauma(
+    mut trauma: ResMut<Trauma>,
+    mut damage_event_reader: EventReader<DamageEvent>,
+    spaceship_query: Query<(), With<Spaceship>>,
+    collider_query: Query<&Collider>,
+) {
+    for event in damage_event_reader.read() {
+        if spaceship_query.contains(event.entity) {
+            trauma.add(SHIP_HIT_TRAUMA);
+        }
+        let killed = event.health_left <= 0.0 && event.health_left + event.amount > 0.0;
+        if let (true, Ok(collider)) = (killed, collider_query.get(event.entity)) {
+            trauma.add(collider.radius * EXPLOSION_TRAUMA_PER_RADIUS);
+        }
+    }
+}
+
+// The fixed view keeps a single ship on the arena and co-op ships framed between them.
+fn fixed_target(ships: &[(Vec3, Vec3)]) -> (Vec3, f32) {
+    if ships.len() > 1 {
+        framing(ships)
+    } else {
+        (Vec3::ZERO, CAMERA_DISTANCE)
+    }
+}
+
+// Following leads the ships by their velocity and pulls back the faster they go.
+fn follow_target(ships: &[(Vec3, Vec3)]) -> (Vec3, f32) {
+    let (center, distance) = framing(ships);
+    let velocity = ships.iter().map(|&(_, velocity)| velocity).sum::<Vec3>() / ships.len() as f32;
+    let zoom = (velocity.length() * ZOOM_PER_SPEED).min(MAX_ZOOM);
+    (center + velocity * LOOKAHEAD_SECONDS, distance + zoom)
+}
+
+fn framing(ships: &[(Vec3, Vec3)]) -> (Vec3, f32) {
+    let center = ships.iter().map(|&(position, _)| position).sum::<Vec3>() / ships.len() as f32;
+    let spread = ships
+        .iter()
+        .map(|&(position, _)| position.distance(center))
+        .fold(0.0, f32::max);
+    (center, CAMERA_DISTANCE.max(spread * 2.0 * FRAMING_SCALE))
+}
+
+fn update_camera_rig(
+    mut rig_query: Query<&mut CameraRig>,
+    spaceship_query: Query<(&Transform, Option<&Velocity>), With<Spaceship>>,
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let ships: Vec<(Vec3, Vec3)> = spaceship_query
+        .iter()
+        .map(|(transform, velocity)| {
+            let velocity = velocity.map(|velocity| velocity.value).unwrap_or_default();
+            (transform.translation, velocity)
+        })
+        .collect();
+    // There is no arena to look down on in the sector world.
+    let mode = if config.sector_world {
+        CameraMode::Follow
+    } else {
+        settings.camera_mode
+    };
+    let (goal, distance, speed) = match mode {
+        CameraMode::Fixed => {
+            let (goal, distance) = fixed_target(&ships);
+            (goal, distance, FRAMING_SPEED)
+        }
+        // Between a ship going down and its respawn the camera holds still.
+        CameraMode::Follow if ships.is_empty() => return,
+        CameraMode::Follow => {
+            let (goal, distance) = follow_target(&ships);
+            (goal, distance, FOLLOW_SPEED)
+        }
+    };
+    let blend = (speed * time.delta_seconds()).min(1.0);
+    let zoom_blend = (FRAMING_SPEED * time.delta_seconds()).min(1.0);
+    for mut rig in rig_query.iter_mut() {
+        rig.focus = rig.focus.lerp(goal, blend);
+        rig.distance += (distance - rig.distance) * zoom_blend;
+    }
+}
+
+fn place_camera(
+    mut camera_query: Query<(&mut Transform, &CameraRig), With<GameCamera>>,
+    mut trauma: ResMut<Trauma>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let shake = trauma.value.powi(2) * settings.screen_shake.max(0.0);
+    let seconds = time.elapsed_seconds() * SHAKE_FREQUENCY;
+    let offset = Vec3::new(noise(seconds, 0.0), 0.0, noise(seconds, 1.0)) * MAX_SHAKE_OFFSET;
+    let angle = noise(seconds, 2.0) * MAX_SHAKE_ANGLE;
+    for (mut transform, rig) in camera_query.iter_mut() {
+        *transform = Transform::from_xyz(rig.focus.x, rig.distance, rig.focus.z)
+            .looking_at(Vec3::new(rig.focus.x, 0.0, rig.focus.z), Vec3::Z);
+        transform.rotate_local_z(angle * shake);
+        transform.translation += offset * shake;
+    }
+    trauma.value = (trauma.value - TRAUMA_DECAY * time.delta_seconds()).max(0.0);
+}
+
+// Smooth wobble in -1..1, each channel out of step with the others.
+fn noise(time: f32, channel: f32) -> f32 {
+    let phase = channel * 17.3;
+    ((time + phase).sin() * 0.6
+        + (time * 2.3 + phase).sin() * 0.3
+        + (time * 5.1 + phase).sin() * 0.1)
+        .clamp(-1.0, 1.0)
+}
+
+fn camera_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [mode] = args else {
+        let mode = world.resource::<Settings>().camera_mode;
+        return Ok(format!("{mode:?}"));
+    };
+    let mode: CameraMode =
+        ron::from_str(mode).map_err(|_| format!("unknown camera mode '{mode}'"))?;
+    world.resource_mut::<Settings>().camera_mode = mode;
+    save_settings(world)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::despawn::{despawn_far_away_entities, PlayArea, DESPAWN_DISTANCE};
+
+    #[test]
+    fn follow_leads_and_zooms_out_with_speed() {
+        let parked = follow_target(&[(Vec3::new(10.0, 0.0, 5.0), Vec3::ZERO)]);
+        assert_eq!(parked, (Vec3::new(10.0, 0.0, 5.0), CAMERA_DISTANCE));
+
+        let (focus, distance) =
+            follow_target(&[(Vec3::new(10.0, 0.0, 5.0), Vec3::new(20.0, 0.0, 0.0))]);
+        assert_eq!(focus, Vec3::new(20.0, 0.0, 5.0));
+        assert!(distance > CAMERA_DISTANCE);
+    }
+
+    #[test]
+    fn trauma_is_capped() {
+        let mut trauma = Trauma::default();
+        trauma.add(SHIP_HIT_TRAUMA);
+        trauma.add(2.0);
+        assert_eq!(trauma.value, 1.0);
+    }
+
+    #[test]
+    fn camera_survives_at_full_zoom() {
+        let mut world = World::new();
+        world.init_resource::<PlayArea>();
+        world.init_resource::<Trauma>();
+        world.init_resource::<GameConfig>();
+        world.insert_resource(Settings {
+            camera_mode: CameraMode::Follow,
+            ..default()
+        });
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs(1));
+        world.insert_resource(time);
+        world.run_system_once(spawn_camera);
+        world.spawn((
+            Spaceship,
+            Transform::default(),
+            Velocity::new(Vec3::X * MAX_ZOOM * 2.0 / ZOOM_PER_SPEED),
+        ));
+
+        for _ in 0..10 {
+            world.run_system_once(update_camera_rig);
+        }
+        world.run_system_once(place_camera);
+        world.run_system_once(despawn_far_away_entities);
+
+        let (transform, rig) = world
+            .query_filtered::<(&Transform, &CameraRig), With<GameCamera>>()
+            .single(&world);
+        assert_eq!(rig.distance, CAMERA_DISTANCE + MAX_ZOOM);
+        assert!(transform.translation.length() > DESPAWN_DISTANCE);
+    }
 }
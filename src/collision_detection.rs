@@ -1,9 +1,28 @@
 use bevy::{prelude::*, utils::HashMap};
 
-#[derive(Component, Debug)]
+use crate::{
+    asteroids::Asteroid,
+    boss::BossPart,
+    health::{DamageEvent, Health, Invulnerable},
+    pool::PoolAppExt,
+    rollback::Rollback,
+    spaceship::{Spaceship, SpaceshipMissile},
+    ufo::{Ufo, UfoProjectile},
+};
+
+// `colliding_entities` is everything touching this frame, `new_contacts` the part of it that
+// raises a `CollisionEvent`. Systems that settle a contact themselves, like the shield, take it
+// out of both lists on both colliders.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component, Default)]
 pub struct Collider {
     pub radius: f32,
+    // Rebuilt every frame, so it is never written to saves or scenes.
+    #[reflect(skip_serializing)]
     pub colliding_entities: Vec<Entity>,
+    // The ones among `colliding_entities` that weren't touching last frame.
+    #[reflect(skip_serializing)]
+    pub new_contacts: Vec<Entity>,
 }
 
 impl Collider {
@@ -11,6 +30,40 @@ impl Collider {
         Self {
             radius,
             colliding_entities: vec![],
+            new_contacts: vec![],
+        }
+    }
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct CollisionDamage {
+    pub amount: f32,
+}
+
+impl CollisionDamage {
+    pub fn new(amount: f32) -> Self {
+        Self { amount }
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct CollisionEvent {
+    pub entity: Entity,
+    pub collided_entity: Entity,
+}
+
+impl CollisionEvent {
+    pub fn new(entity: Entity, collided_entity: Entity) -> Self {
+        Self {
+            entity,
+            collided_entity,
         }
     }
 }
@@ -19,11 +72,36 @@ pub struct CollisionDetectionPlugin;
 
 impl Plugin for CollisionDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, collision_detection);
+        app.register_type::<Collider>()
+            .register_type::<CollisionDamage>()
+            .register_type::<Health>()
+            .register_type::<Invulnerable>()
+            .reset_on_retire::<Invulnerable>()
+            .add_event::<CollisionEvent>()
+            .add_event::<DamageEvent>()
+            .add_systems(
+                Update,
+                (
+                    collision_detection,
+                    (
+                        handle_collisions::<Asteroid>,
+                        handle_collisions::<Spaceship>,
+                        handle_collisions::<SpaceshipMissile>,
+                        handle_collisions::<Ufo>,
+                        handle_collisions::<UfoProjectile>,
+                        handle_collisions::<BossPart>,
+                    ),
+                    apply_collision_damage,
+                )
+                    .chain(),
+            );
     }
 }
 
-fn collision_detection(mut query: Query<(Entity, &GlobalTransform, &mut Collider)>) {
+// Rolled back entities detect their own collisions on every tick.
+pub fn collision_detection(
+    mut query: Query<(Entity, &GlobalTransform, &mut Collider), Without<Rollback>>,
+) {
     let mut colliding_entities: HashMap<Entity, Vec<Entity>> = HashMap::new();
 
     // First phase: Detect collisions.
@@ -36,7 +114,7 @@ fn collision_detection(mut query: Query<(Entity, &GlobalTransform, &mut Collider
                 if distance < collider_a.radius + collider_b.radius {
                     colliding_entities
                         .entry(entity_a)
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(entity_b);
                 }
             }
@@ -45,11 +123,103 @@ fn collision_detection(mut query: Query<(Entity, &GlobalTransform, &mut Collider
 
     // Second phase: Update colliders.
     for (entity, _, mut collider) in query.iter_mut() {
-        collider.colliding_entities.clear();
-        if let Some(collisions) = colliding_entities.get(&entity) {
-            collider
-                .colliding_entities
-                .extend(collisions.iter().copied());
+        let collisions = colliding_entities.remove(&entity).unwrap_or_default();
+        collider.new_contacts = collisions
+            .iter()
+            .copied()
+            .filter(|collided| !collider.colliding_entities.contains(collided))
+            .collect();
+        collider.colliding_entities = collisions;
+    }
+}
+
+// Overlapping colliders only collide once, on the frame they first touch.
+pub fn handle_collisions<T: Component>(
+    mut collision_event_writer: EventWriter<CollisionEvent>,
+    query: Query<(Entity, &Collider), With<T>>,
+) {
+    for (entity, collider) in query.iter() {
+        for &collided_entity in collider.new_contacts.iter() {
+            // Entity collided with another entity of the same type.
+            if query.get(collided_entity).is_ok() {
+                continue;
+            }
+            collision_event_writer.send(CollisionEvent::new(entity, collided_entity));
         }
     }
 }
+
+pub fn apply_collision_damage(
+    mut collision_event_reader: EventReader<CollisionEvent>,
+    mut damage_event_writer: EventWriter<DamageEvent>,
+    mut health_query: Query<&mut Health, Without<Invulnerable>>,
+    collision_damage_query: Query<&CollisionDamage>,
+) {
+    for &CollisionEvent {
+        entity,
+        collided_entity,
+    } in collision_event_reader.read()
+    {
+        let Ok(mut health) = health_query.get_mut(entity) else {
+            continue;
+        };
+        let Ok(collision_damage) = collision_damage_query.get(collided_entity) else {
+            continue;
+        };
+        health.value -= collision_damage.amount;
+        damage_event_writer.send(DamageEvent {
+            entity,
+            source: collided_entity,
+            amount: collision_damage.amount,
+            health_left: health.value,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn overlapping_colliders_only_collide_once() {
+        let mut world = World::new();
+        world.init_resource::<Events<CollisionEvent>>();
+        world.spawn((
+            GlobalTransform::IDENTITY,
+            Collider::new(1.0),
+            Asteroid::default(),
+        ));
+        let ship = world
+            .spawn((GlobalTransform::IDENTITY, Collider::new(1.0), Spaceship))
+            .id();
+
+        let mut collisions = 0;
+        for _ in 0..3 {
+            world.run_system_once(collision_detection);
+            world.run_system_once(handle_collisions::<Asteroid>);
+            collisions += world
+                .resource_mut::<Events<CollisionEvent>>()
+                .drain()
+                .count();
+        }
+        assert_eq!(collisions, 1);
+
+        // Moving apart and touching again is a new contact.
+        world
+            .entity_mut(ship)
+            .insert(GlobalTransform::from_xyz(5.0, 0.0, 0.0));
+        world.run_system_once(collision_detection);
+        world.entity_mut(ship).insert(GlobalTransform::IDENTITY);
+        world.run_system_once(collision_detection);
+        world.run_system_once(handle_collisions::<Asteroid>);
+        assert_eq!(
+            world
+                .resource_mut::<Events<CollisionEvent>>()
+                .drain()
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::{fmt, path::Path};
+
+use bevy::{
+    prelude::*,
+    reflect::{GetPath, Struct},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    console::ConsoleAppExt,
+    ron_file::{self, RonError},
+};
+
+// Tunables that can be overridden with `--config <FILE>`. Missing keys keep their defaults.
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct GameConfig {
+    pub clear_color: [f32; 3],
+    pub ambient_brightness: f32,
+    pub window_width: f32,
+    pub window_height: f32,
+    pub asteroid_spawn_seconds: f32,
+    pub wave_seconds: f32,
+    pub procedural_asteroids: bool,
+    pub ufo_spawn_seconds: f32,
+    // 1.0 aims every UFO shot straight at where the spaceship is heading.
+    pub ufo_accuracy: f32,
+    // A boss turns up on every wave that is a multiple of this, 0 turns bosses off.
+    pub boss_wave_interval: u32,
+    // Chance that an asteroid shot down by the spaceship drops a power-up.
+    pub powerup_drop_chance: f32,
+    // Each player flies their own ship with their own bindings, see `Settings`.
+    pub players: u32,
+    // Extra lives only come from pickups unless this is raised.
+    pub starting_lives: u32,
+    pub hyperspace_failure_chance: f32,
+    // Replaces the arena with a large world streamed in sector by sector around the ships.
+    pub sector_world: bool,
+    // How many asteroids a sector holds on average, each sector is denser or sparser.
+    pub sector_asteroids: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            clear_color: [0.1, 0.0, 0.15],
+            ambient_brightness: 0.75,
+            window_width: 1280.0,
+            window_height: 720.0,
+            asteroid_spawn_seconds: 1.0,
+            wave_seconds: 30.0,
+            procedural_asteroids: false,
+            ufo_spawn_seconds: 20.0,
+            ufo_accuracy: 0.6,
+            boss_wave_interval: 5,
+            powerup_drop_chance: 0.2,
+            players: 1,
+            starting_lives: 1,
+            hyperspace_failure_chance: 0.05,
+            sector_world: false,
+            sector_asteroids: 12.0,
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let config: Self = ron_file::read(path)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Spawn timers can't run on an interval of zero or less.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let intervals = [
+            ("asteroid_spawn_seconds", self.asteroid_spawn_seconds),
+            ("ufo_spawn_seconds", self.ufo_spawn_seconds),
+            ("wave_seconds", self.wave_seconds),
+        ];
+        for (key, seconds) in intervals {
+            if !(seconds.is_finite() && seconds > 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "{key} must be a positive number of seconds, not {seconds}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear_color(&self) -> Color {
+        let [r, g, b] = self.clear_color;
+        Color::rgb(r, g, b)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    File(RonError),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::File(err) => write!(f, "could not load config: {err}"),
+            ConfigError::Invalid(reason) => write!(f, "invalid config: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<RonError> for ConfigError {
+    fn from(err: RonError) -> Self {
+        ConfigError::File(err)
+    }
+}
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameConfig>()
+            .register_type::<GameConfig>()
+            .add_systems(Update, apply_config)
+            .add_console_command(
+                "set",
+                "set <key> <value>: change a config value, or list them all",
+                set_command,
+            );
+    }
+}
+
+fn apply_config(
+    config: Res<GameConfig>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient_light: ResMut<AmbientLight>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    clear_color.0 = config.clear_color();
+    ambient_light.brightness = config.ambient_brightness;
+}
+
+fn set_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let mut config = world.resource::<GameConfig>().clone();
+    let Some((key, value)) = args.split_first() else {
+        return Ok((0..config.field_len())
+            .filter_map(|index| Some((config.name_at(index)?, config.field_at(index)?)))
+            .map(|(name, value)| format!("{name} = {value:?}"))
+            .collect::<Vec<_>>()
+            .join("\n"));
+    };
+    let value = value.join(" ");
+    let field = config
+        .reflect_path_mut(*key)
+        .map_err(|err| format!("unknown key '{key}': {err}"))?;
+
+    let invalid_value = || format!("'{value}' is not a valid value for {key}");
+    if let Some(field) = field.downcast_mut::<f32>() {
+        *field = value.parse().map_err(|_| invalid_value())?;
+    } else if let Some(field) = field.downcast_mut::<u32>() {
+        *field = value.parse().map_err(|_| invalid_value())?;
+    } else if let Some(field) = field.downcast_mut::<bool>() {
+        *field = value.parse().map_err(|_| invalid_value())?;
+    } else if let Some(field) = field.downcast_mut::<[f32; 3]>() {
+        *field = ron::from_str(&value).map_err(|_| invalid_value())?;
+    } else {
+        return Err(format!("{key} cannot be set from the console"));
+    }
+    config.validate().map_err(|err| err.to_string())?;
+    *world.resource_mut::<GameConfig>() = config;
+    Ok(format!("{key} = {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_intervals_must_be_positive() {
+        assert!(GameConfig::default().validate().is_ok());
+        let config: GameConfig = ron::from_str("(asteroid_spawn_seconds: 0.0)").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+        let config: GameConfig = ron::from_str("(wave_seconds: -1.0)").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        let mut world = World::new();
+        world.init_resource::<GameConfig>();
+        let default_seconds = GameConfig::default().asteroid_spawn_seconds;
+        assert!(set_command(&mut world, &["asteroid_spawn_seconds", "-1"]).is_err());
+        assert_eq!(
+            world.resource::<GameConfig>().asteroid_spawn_seconds,
+            default_seconds
+        );
+        assert!(set_command(&mut world, &["asteroid_spawn_seconds", "5"]).is_ok());
+        assert_eq!(world.resource::<GameConfig>().asteroid_spawn_seconds, 5.0);
+        assert!(set_command(&mut world, &["ufo_spawn_seconds", "0"]).is_err());
+    }
+}
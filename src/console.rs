@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use bevy::{input::InputSystem, prelude::*, window::ReceivedCharacter};
+
+use crate::action::ActionSystem;
+
+const TOGGLE_KEY: KeyCode = KeyCode::Grave;
+const MAX_LOG_LINES: usize = 12;
+const FONT_SIZE: f32 = 16.0;
+const BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+
+// A command gets the world and the words after its name, and returns the text to print.
+pub type ConsoleHandler = fn(&mut World, &[&str]) -> Result<String, String>;
+
+#[derive(Debug, Clone, Copy)]
+struct ConsoleCommand {
+    help: &'static str,
+    handler: ConsoleHandler,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<String, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    // Command names may span several words (`spawn asteroid`), so the longest match wins.
+    fn find(&self, words: &[&str]) -> Option<(ConsoleCommand, usize)> {
+        (1..=words.len()).rev().find_map(|len| {
+            self.commands
+                .get(&words[..len].join(" "))
+                .map(|command| (*command, len))
+        })
+    }
+}
+
+pub trait ConsoleAppExt {
+    fn add_console_command(
+        &mut self,
+        name: &str,
+        help: &'static str,
+        handler: ConsoleHandler,
+    ) -> &mut Self;
+}
+
+impl ConsoleAppExt for App {
+    fn add_console_command(
+        &mut self,
+        name: &str,
+        help: &'static str,
+        handler: ConsoleHandler,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .commands
+            .insert(name.to_string(), ConsoleCommand { help, handler });
+        self
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct ConsoleState {
+    pub open: bool,
+    input: String,
+    log: Vec<String>,
+    pending: Vec<String>,
+}
+
+#[derive(Component, Debug)]
+struct ConsoleRoot;
+
+#[derive(Component, Debug)]
+struct ConsoleText;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleCommands>()
+            .init_resource::<ConsoleState>()
+            .add_console_command("help", "list commands", help_command)
+            .add_console_command("clear", "clear the console", clear_command)
+            .add_console_command(
+                "timescale",
+                "timescale <ratio>: slow down or speed up the game",
+                timescale_command,
+            )
+            .add_systems(Startup, spawn_console)
+            .add_systems(
+                PreUpdate,
+                (toggle_console, read_console_input)
+                    .chain()
+                    .after(InputSystem)
+                    .before(ActionSystem),
+            )
+            .add_systems(Update, (run_console_commands, update_console).chain());
+    }
+}
+
+fn spawn_console(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(40.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexEnd,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(100),
+                ..default()
+            },
+            ConsoleRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: FONT_SIZE,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+fn toggle_console(mut console: ResMut<ConsoleState>, keyboard_input: Res<Input<KeyCode>>) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        console.open = !console.open;
+    }
+}
+
+fn read_console_input(
+    mut console: ResMut<ConsoleState>,
+    mut character_events: EventReader<ReceivedCharacter>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+) {
+    if !console.open {
+        character_events.clear();
+        return;
+    }
+
+    for event in character_events.read() {
+        if event.char != '`' && !event.char.is_control() {
+            console.input.push(event.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        if !line.trim().is_empty() {
+            console.pending.push(line);
+        }
+    }
+
+    // Keys typed into the console must not also fly the ship.
+    keyboard_input.reset_all();
+}
+
+fn run_console_commands(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<ConsoleState>().pending);
+    for line in pending {
+        let output = run_command(world, &line);
+        let mut console = world.resource_mut::<ConsoleState>();
+        console.log.push(format!("> {line}"));
+        match output {
+            Ok(text) => console.log.extend(text.lines().map(String::from)),
+            Err(text) => console.log.push(format!("error: {text}")),
+        }
+    }
+}
+
+pub fn run_command(world: &mut World, line: &str) -> Result<String, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (command, name_len) = world
+        .resource::<ConsoleCommands>()
+        .find(&words)
+        .ok_or_else(|| format!("unknown command '{}', try 'help'", line.trim()))?;
+    (command.handler)(world, &words[name_len..])
+}
+
+fn update_console(
+    console: Res<ConsoleState>,
+    mut root_query: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut visibility in root_query.iter_mut() {
+        *visibility = if console.open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+    let first_line = console.log.len().saturating_sub(MAX_LOG_LINES);
+    let mut text_value = console.log[first_line..].join("\n");
+    text_value.push_str(&format!("\n> {}_", console.input));
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = text_value.clone();
+    }
+}
+
+fn help_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let commands = world.resource::<ConsoleCommands>();
+    Ok(commands
+        .commands
+        .iter()
+        .map(|(name, command)| format!("{name} - {}", command.help))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn clear_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    world.resource_mut::<ConsoleState>().log.clear();
+    Ok(String::new())
+}
+
+fn timescale_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [ratio] = args else {
+        return Err("usage: timescale <ratio>".into());
+    };
+    let ratio: f32 = ratio
+        .parse()
+        .map_err(|_| format!("'{ratio}' is not a number"))?;
+    if !ratio.is_finite() || ratio < 0.0 {
+        return Err("ratio must be a non-negative number".into());
+    }
+    world
+        .resource_mut::<Time<Virtual>>()
+        .set_relative_speed(ratio);
+    Ok(format!("timescale set to {ratio}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(_world: &mut World, args: &[&str]) -> Result<String, String> {
+        Ok(args.join(" "))
+    }
+
+    fn spawn(_world: &mut World, _args: &[&str]) -> Result<String, String> {
+        Ok("spawn".into())
+    }
+
+    fn world_with_commands() -> World {
+        let mut app = App::new();
+        app.add_console_command("spawn", "", spawn)
+            .add_console_command("spawn asteroid", "", echo);
+        std::mem::take(&mut app.world)
+    }
+
+    #[test]
+    fn longest_name_wins() {
+        let mut world = world_with_commands();
+        assert_eq!(
+            run_command(&mut world, "spawn asteroid 1 2"),
+            Ok("1 2".into())
+        );
+        assert_eq!(run_command(&mut world, "spawn ufo"), Ok("spawn".into()));
+    }
+
+    #[test]
+    fn unknown_command() {
+        let mut world = world_with_commands();
+        assert!(run_command(&mut world, "fly").is_err());
+        assert!(run_command(&mut world, "").is_err());
+    }
+}
@@ -1,19 +1,184 @@
-use bevy::prelude::*;
+use bevy::{
+    diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use crate::{
+    asteroids::{Asteroid, SPAWN_RANGE_X, SPAWN_RANGE_Z},
+    collision_detection::Collider,
+    console::ConsoleAppExt,
+    despawn::{PlayArea, DESPAWN_DISTANCE},
+    movement::{Acceleration, Velocity},
+    pool::EntityPool,
+    spaceship::{Spaceship, SpaceshipMissile},
+};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+const COLLIDER_COLOR: Color = Color::GREEN;
+const VELOCITY_COLOR: Color = Color::CYAN;
+const ACCELERATION_COLOR: Color = Color::ORANGE;
+const SPAWN_ZONE_COLOR: Color = Color::YELLOW;
+const DESPAWN_BOUNDARY_COLOR: Color = Color::RED;
+const DESPAWN_BOUNDARY_SEGMENTS: usize = 64;
+const READOUT_FONT_SIZE: f32 = 18.0;
+
+#[derive(Resource, Debug, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+#[derive(Component, Debug)]
+struct DebugReadout;
 
 pub struct DebugPlugin;
 
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, print_position);
+        app.init_resource::<DebugOverlay>()
+            .add_plugins((FrameTimeDiagnosticsPlugin, EntityCountDiagnosticsPlugin))
+            .add_systems(Startup, spawn_readout)
+            .add_systems(Update, toggle_overlay)
+            .add_console_command("entities", "count entities by type", entities_command)
+            .add_systems(
+                Update,
+                (
+                    draw_colliders,
+                    draw_motion_vectors,
+                    draw_play_area,
+                    update_readout,
+                )
+                    .run_if(overlay_enabled),
+            );
+    }
+}
+
+fn overlay_enabled(overlay: Res<DebugOverlay>) -> bool {
+    overlay.enabled
+}
+
+fn toggle_overlay(
+    mut overlay: ResMut<DebugOverlay>,
+    mut readout_query: Query<&mut Visibility, With<DebugReadout>>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        overlay.enabled = !overlay.enabled;
+    }
+    if !overlay.is_changed() {
+        return;
     }
+    for mut visibility in readout_query.iter_mut() {
+        *visibility = if overlay.enabled {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn spawn_readout(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: READOUT_FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        DebugReadout,
+    ));
 }
 
-fn print_position(query: Query<(Entity, &Transform)>) {
-    // Log the entity ID and translation of each entity with a `Position` component.
-    for (entity, transform) in query.iter() {
-        info!(
-            "Entity {:?} is at position {:?},",
-            entity, transform.translation
+fn draw_colliders(mut gizmos: Gizmos, query: Query<(&GlobalTransform, &Collider)>) {
+    for (transform, collider) in query.iter() {
+        gizmos.circle(
+            transform.translation(),
+            Vec3::Y,
+            collider.radius,
+            COLLIDER_COLOR,
         );
     }
 }
+
+fn draw_motion_vectors(
+    mut gizmos: Gizmos,
+    query: Query<(&GlobalTransform, &Velocity, Option<&Acceleration>)>,
+) {
+    for (transform, velocity, acceleration) in query.iter() {
+        let position = transform.translation();
+        gizmos.ray(position, velocity.value, VELOCITY_COLOR);
+        if let Some(acceleration) = acceleration {
+            gizmos.ray(position, acceleration.value, ACCELERATION_COLOR);
+        }
+    }
+}
+
+fn draw_play_area(mut gizmos: Gizmos, play_area: Res<PlayArea>) {
+    let center = play_area.center;
+    let corners = [
+        Vec3::new(SPAWN_RANGE_X.start, 0.0, SPAWN_RANGE_Z.start),
+        Vec3::new(SPAWN_RANGE_X.end, 0.0, SPAWN_RANGE_Z.start),
+        Vec3::new(SPAWN_RANGE_X.end, 0.0, SPAWN_RANGE_Z.end),
+        Vec3::new(SPAWN_RANGE_X.start, 0.0, SPAWN_RANGE_Z.end),
+        Vec3::new(SPAWN_RANGE_X.start, 0.0, SPAWN_RANGE_Z.start),
+    ];
+    gizmos.linestrip(corners.map(|corner| center + corner), SPAWN_ZONE_COLOR);
+
+    gizmos
+        .circle(center, Vec3::Y, DESPAWN_DISTANCE, DESPAWN_BOUNDARY_COLOR)
+        .segments(DESPAWN_BOUNDARY_SEGMENTS);
+}
+
+fn update_readout(
+    mut readout_query: Query<&mut Text, With<DebugReadout>>,
+    diagnostics: Res<DiagnosticsStore>,
+    asteroid_query: Query<(), With<Asteroid>>,
+    missile_query: Query<(), With<SpaceshipMissile>>,
+    pool: Res<EntityPool>,
+) {
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let entities = diagnostics
+        .get(EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+        .and_then(|count| count.value())
+        .unwrap_or_default();
+
+    for mut text in readout_query.iter_mut() {
+        text.sections[0].value = format!(
+            "FPS: {fps:.0}\nEntities: {entities:.0}\nAsteroids: {}\nMissiles: {}\nPool: {} hits, {} misses, {} free",
+            asteroid_query.iter().count(),
+            missile_query.iter().count(),
+            pool.hits,
+            pool.misses,
+            pool.free_count(),
+        );
+    }
+}
+
+fn entities_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let total = world.entities().len();
+    let spaceships = world
+        .query_filtered::<(), With<Spaceship>>()
+        .iter(world)
+        .count();
+    let asteroids = world
+        .query_filtered::<(), With<Asteroid>>()
+        .iter(world)
+        .count();
+    let missiles = world
+        .query_filtered::<(), With<SpaceshipMissile>>()
+        .iter(world)
+        .count();
+    Ok(format!(
+        "total: {total}\nspaceships: {spaceships}\nasteroids: {asteroids}\nmissiles: {missiles}"
+    ))
+}
@@ -1,25 +1,129 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::Command, prelude::*};
 
-use crate::spaceship::Spaceship;
+use crate::{
+    camera::GameCamera,
+    collision_detection::apply_collision_damage,
+    console::ConsoleAppExt,
+    health::Health,
+    movement::Velocity,
+    pool::{PoolCommandsExt, Retire},
+    sector::SectorMember,
+    spaceship::Spaceship,
+    state::GameState,
+};
 
-const DESPAWN_DISTANCE: f32 = 100.0;
+pub const DESPAWN_DISTANCE: f32 = 100.0;
+
+// Where the action is, the origin unless the sector world moves it along with the ships.
+#[derive(Resource, Debug, Default)]
+pub struct PlayArea {
+    pub center: Vec3,
+}
 
 pub struct DespawnPlugin;
 
 impl Plugin for DespawnPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, despawn_far_away_entities);
+        app.init_resource::<PlayArea>()
+            .add_systems(
+                Update,
+                (
+                    despawn_far_away_entities,
+                    despawn_dead_entities.after(apply_collision_damage),
+                ),
+            )
+            .add_systems(OnEnter(GameState::GameOver), despawn_all_entities)
+            .add_console_command(
+                "kill_all",
+                "destroy everything except the spaceship",
+                kill_all_command,
+            );
     }
 }
 
-fn despawn_far_away_entities(mut commands: Commands, query: Query<(Entity, &GlobalTransform),Without<Spaceship>>) {
+// Only things that move through the play area; UI roots and cameras have transforms too. Scene
+// children are removed along with their root, and pooled entities keep theirs. Sector asteroids
+// go when their sector is unloaded.
+type FarAwayFilter = (
+    With<Velocity>,
+    Without<Node>,
+    Without<GameCamera>,
+    Without<Spaceship>,
+    Without<Parent>,
+    Without<SectorMember>,
+);
+
+pub(crate) fn despawn_far_away_entities(
+    mut commands: Commands,
+    query: Query<(Entity, &GlobalTransform), FarAwayFilter>,
+    play_area: Res<PlayArea>,
+) {
     for (entity, transform) in query.iter() {
-        let distance = transform.translation().distance(Vec3::ZERO);
+        let distance = transform.translation().distance(play_area.center);
 
         // Entity is far away from the camera's viewport.
         if distance > DESPAWN_DISTANCE {
-            commands.entity(entity).despawn_recursive();
-            // commands.entity(entity).despawn();
+            commands.retire(entity);
+        }
+    }
+}
+
+fn despawn_dead_entities(mut commands: Commands, query: Query<(Entity, &Health)>) {
+    for (entity, health) in query.iter() {
+        // Entity doesn't have any health left.
+        if health.value <= 0.0 {
+            commands.retire(entity);
         }
     }
 }
+
+fn despawn_all_entities(mut commands: Commands, query: Query<Entity, With<Health>>) {
+    for entity in query.iter() {
+        commands.retire(entity);
+    }
+}
+
+fn kill_all_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, (With<Health>, Without<Spaceship>)>()
+        .iter(world)
+        .collect();
+    for &entity in entities.iter() {
+        Retire(entity).apply(world);
+    }
+    Ok(format!("destroyed {} entities", entities.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn far_away_ui_and_cameras_survive() {
+        let mut world = World::new();
+        world.init_resource::<PlayArea>();
+        let far_away = Transform::from_xyz(DESPAWN_DISTANCE * 2.0, 0.0, 0.0);
+        let ui_root = world.spawn(NodeBundle::default()).id();
+        world
+            .entity_mut(ui_root)
+            .insert(GlobalTransform::from(far_away));
+        let camera = world
+            .spawn(Camera3dBundle {
+                transform: far_away,
+                global_transform: GlobalTransform::from(far_away),
+                ..default()
+            })
+            .id();
+        let rock = world
+            .spawn((GlobalTransform::from(far_away), Velocity::new(Vec3::ZERO)))
+            .id();
+
+        world.run_system_once(despawn_far_away_entities);
+
+        assert!(world.get_entity(ui_root).is_some());
+        assert!(world.get_entity(camera).is_some());
+        assert!(world.get_entity(rock).is_none());
+    }
+}
@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct Health {
+    pub value: f32,
+}
+
+impl Health {
+    pub fn new(value: f32) -> Self {
+        Self { value }
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+// Entities with this marker ignore collision damage.
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct Invulnerable;
+
+#[derive(Event, Debug)]
+pub struct DamageEvent {
+    pub entity: Entity,
+    pub source: Entity,
+    pub amount: f32,
+    pub health_left: f32,
+}
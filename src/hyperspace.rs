@@ -0,0 +1,201 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    action::{Action, PlayerActions},
+    collision_detection::Collider,
+    config::GameConfig,
+    despawn::PlayArea,
+    health::{Health, Invulnerable},
+    rng::GameRng,
+    spaceship::{Player, Spaceship, SPACESHIP_RADIUS},
+    state::GameState,
+};
+
+const WARP_SECONDS: f32 = 0.5;
+const COOLDOWN_SECONDS: f32 = 6.0;
+// The part of the field that is on screen, jumps never land outside it.
+const PLAYFIELD_X: Range<f32> = -40.0..40.0;
+const PLAYFIELD_Z: Range<f32> = -25.0..25.0;
+// Extra room kept between the ship and anything it could land on.
+const SAFE_MARGIN: f32 = 4.0;
+const MAX_ATTEMPTS: usize = 32;
+
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct Hyperspace {
+    // Seconds left until the ship reappears, zero when not jumping.
+    pub warp: f32,
+    pub cooldown: f32,
+}
+
+type JumpingShip<'a> = (
+    Entity,
+    &'a mut Hyperspace,
+    &'a mut Transform,
+    &'a mut Health,
+    Has<Invulnerable>,
+);
+
+pub struct HyperspacePlugin;
+
+impl Plugin for HyperspacePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Hyperspace>().add_systems(
+            Update,
+            (attach_hyperspace, hyperspace_controls, hyperspace_jump)
+                .chain()
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+fn attach_hyperspace(mut commands: Commands, query: Query<Entity, Added<Spaceship>>) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(Hyperspace::default());
+    }
+}
+
+fn hyperspace_controls(
+    mut query: Query<(&Player, &mut Hyperspace), With<Spaceship>>,
+    player_actions: Res<PlayerActions>,
+    time: Res<Time>,
+) {
+    for (player, mut hyperspace) in query.iter_mut() {
+        hyperspace.cooldown = (hyperspace.cooldown - time.delta_seconds()).max(0.0);
+        if player_actions
+            .get(player.id)
+            .just_pressed(Action::Hyperspace)
+            && hyperspace.warp <= 0.0
+            && hyperspace.cooldown <= 0.0
+        {
+            hyperspace.warp = WARP_SECONDS;
+        }
+    }
+}
+
+// The ship shrinks away while warping out, then either reappears somewhere clear or, once in
+// a while, doesn't make it.
+fn hyperspace_jump(
+    mut spaceship_query: Query<JumpingShip, With<Spaceship>>,
+    collider_query: Query<(Entity, &GlobalTransform, &Collider)>,
+    config: Res<GameConfig>,
+    play_area: Res<PlayArea>,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+) {
+    for (entity, mut hyperspace, mut transform, mut health, invulnerable) in
+        spaceship_query.iter_mut()
+    {
+        if hyperspace.warp <= 0.0 {
+            continue;
+        }
+        hyperspace.warp -= time.delta_seconds();
+        if hyperspace.warp > 0.0 {
+            transform.scale = Vec3::splat(hyperspace.warp / WARP_SECONDS);
+            continue;
+        }
+
+        hyperspace.warp = 0.0;
+        hyperspace.cooldown = COOLDOWN_SECONDS;
+        transform.scale = Vec3::ONE;
+        // The roll is made either way so invulnerability doesn't change what comes after it.
+        let failed = rng.gen_bool(config.hyperspace_failure_chance.clamp(0.0, 1.0) as f64);
+        if failed && !invulnerable {
+            info!("Hyperspace jump failed");
+            health.value = 0.0;
+            continue;
+        }
+
+        // The playfield is laid out around the play area's centre.
+        let obstacles: Vec<(Vec3, f32)> = collider_query
+            .iter()
+            .filter(|(other, _, _)| *other != entity)
+            .map(|(_, transform, collider)| {
+                (transform.translation() - play_area.center, collider.radius)
+            })
+            .collect();
+        match find_safe_point(&mut **rng, &obstacles) {
+            Some(point) => transform.translation = play_area.center + point,
+            None => info!("Hyperspace found nowhere safe to land"),
+        }
+    }
+}
+
+pub fn find_safe_point(rng: &mut impl Rng, obstacles: &[(Vec3, f32)]) -> Option<Vec3> {
+    (0..MAX_ATTEMPTS)
+        .map(|_| Vec3::new(rng.gen_range(PLAYFIELD_X), 0.0, rng.gen_range(PLAYFIELD_Z)))
+        .find(|point| {
+            obstacles.iter().all(|&(position, radius)| {
+                point.distance(position) > radius + SPACESHIP_RADIUS + SAFE_MARGIN
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn safe_points_stay_clear_of_obstacles() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let obstacles: Vec<(Vec3, f32)> = (0..20)
+            .map(|i| (Vec3::new(i as f32 * 4.0 - 40.0, 0.0, 0.0), 3.0))
+            .collect();
+        for _ in 0..50 {
+            let point = find_safe_point(&mut rng, &obstacles).unwrap();
+            assert!(PLAYFIELD_X.contains(&point.x) && PLAYFIELD_Z.contains(&point.z));
+            for &(position, radius) in obstacles.iter() {
+                assert!(point.distance(position) > radius + SPACESHIP_RADIUS + SAFE_MARGIN);
+            }
+        }
+    }
+
+    #[test]
+    fn no_safe_point_in_a_full_field() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        assert_eq!(find_safe_point(&mut rng, &[(Vec3::ZERO, 100.0)]), None);
+    }
+
+    #[test]
+    fn failed_jumps_spare_invulnerable_ships() {
+        let mut world = World::new();
+        world.insert_resource(GameConfig {
+            hyperspace_failure_chance: 1.0,
+            ..default()
+        });
+        world.init_resource::<PlayArea>();
+        world.insert_resource(GameRng::new(7));
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs(1));
+        world.insert_resource(time);
+        let warp = || Hyperspace {
+            warp: WARP_SECONDS,
+            cooldown: 0.0,
+        };
+        let mortal = world
+            .spawn((Spaceship, warp(), Transform::default(), Health::new(3.0)))
+            .id();
+        let god = world
+            .spawn((
+                Spaceship,
+                warp(),
+                Transform::default(),
+                Health::new(3.0),
+                Invulnerable,
+            ))
+            .id();
+
+        world.run_system_once(hyperspace_jump);
+        assert_eq!(world.get::<Health>(mortal).unwrap().value, 0.0);
+        assert_eq!(world.get::<Health>(god).unwrap().value, 3.0);
+    }
+}
@@ -0,0 +1,539 @@
+use bevy::{ecs::query::WorldQuery, prelude::*};
+
+use crate::{
+    asteroids::Asteroid,
+    camera::CursorPosition,
+    collision_detection::Collider,
+    movement::{Acceleration, Velocity},
+    spaceship::{Spaceship, SpaceshipMissile},
+};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F4;
+const PANEL_WIDTH: f32 = 340.0;
+const FONT_SIZE: f32 = 15.0;
+const LISTED_PER_KIND: usize = 6;
+const PANEL_COLOR: Color = Color::rgba(0.05, 0.05, 0.1, 0.85);
+const BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.3);
+const BUTTON_HOVER_COLOR: Color = Color::rgb(0.3, 0.3, 0.45);
+const SELECTION_COLOR: Color = Color::WHITE;
+
+#[derive(Resource, Debug, Default)]
+pub struct InspectorState {
+    pub open: bool,
+    pub selected: Option<Entity>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Spaceship,
+    Asteroid,
+    Missile,
+}
+
+impl Kind {
+    const ALL: [Kind; 3] = [Kind::Spaceship, Kind::Asteroid, Kind::Missile];
+
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Spaceship => "Ship",
+            Kind::Asteroid => "Asteroids",
+            Kind::Missile => "Missiles",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    TranslationX,
+    TranslationY,
+    TranslationZ,
+    Yaw,
+    Scale,
+    VelocityX,
+    VelocityY,
+    VelocityZ,
+    AccelerationX,
+    AccelerationY,
+    AccelerationZ,
+    ColliderRadius,
+}
+
+impl Field {
+    const ALL: [Field; 12] = [
+        Field::TranslationX,
+        Field::TranslationY,
+        Field::TranslationZ,
+        Field::Yaw,
+        Field::Scale,
+        Field::VelocityX,
+        Field::VelocityY,
+        Field::VelocityZ,
+        Field::AccelerationX,
+        Field::AccelerationY,
+        Field::AccelerationZ,
+        Field::ColliderRadius,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Field::TranslationX => "translation.x",
+            Field::TranslationY => "translation.y",
+            Field::TranslationZ => "translation.z",
+            Field::Yaw => "yaw (deg)",
+            Field::Scale => "scale",
+            Field::VelocityX => "velocity.x",
+            Field::VelocityY => "velocity.y",
+            Field::VelocityZ => "velocity.z",
+            Field::AccelerationX => "acceleration.x",
+            Field::AccelerationY => "acceleration.y",
+            Field::AccelerationZ => "acceleration.z",
+            Field::ColliderRadius => "collider.radius",
+        }
+    }
+
+    fn step(self) -> f32 {
+        match self {
+            Field::Yaw => 15.0,
+            Field::Scale => 0.1,
+            Field::AccelerationX | Field::AccelerationY | Field::AccelerationZ => 0.5,
+            Field::ColliderRadius => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    fn value(self, item: &InspectedItem) -> Option<f32> {
+        let transform = item.transform.as_deref();
+        let velocity = item.velocity.as_deref().map(|velocity| velocity.value);
+        let acceleration = item.acceleration.as_deref().map(|accel| accel.value);
+        match self {
+            Field::TranslationX => transform.map(|transform| transform.translation.x),
+            Field::TranslationY => transform.map(|transform| transform.translation.y),
+            Field::TranslationZ => transform.map(|transform| transform.translation.z),
+            Field::Yaw => {
+                transform.map(|transform| transform.rotation.to_euler(EulerRot::YXZ).0.to_degrees())
+            }
+            Field::Scale => transform.map(|transform| transform.scale.x),
+            Field::VelocityX => velocity.map(|value| value.x),
+            Field::VelocityY => velocity.map(|value| value.y),
+            Field::VelocityZ => velocity.map(|value| value.z),
+            Field::AccelerationX => acceleration.map(|value| value.x),
+            Field::AccelerationY => acceleration.map(|value| value.y),
+            Field::AccelerationZ => acceleration.map(|value| value.z),
+            Field::ColliderRadius => item.collider.as_deref().map(|collider| collider.radius),
+        }
+    }
+
+    fn nudge(self, item: &mut InspectedItem, direction: f32) {
+        let delta = self.step() * direction;
+        match self {
+            Field::TranslationX | Field::TranslationY | Field::TranslationZ => {
+                if let Some(transform) = item.transform.as_deref_mut() {
+                    transform.translation[self as usize - Field::TranslationX as usize] += delta;
+                }
+            }
+            Field::Yaw => {
+                if let Some(transform) = item.transform.as_deref_mut() {
+                    transform.rotate_y(delta.to_radians());
+                }
+            }
+            Field::Scale => {
+                if let Some(transform) = item.transform.as_deref_mut() {
+                    transform.scale = (transform.scale + Vec3::splat(delta)).max(Vec3::splat(0.1));
+                }
+            }
+            Field::VelocityX | Field::VelocityY | Field::VelocityZ => {
+                if let Some(velocity) = item.velocity.as_deref_mut() {
+                    velocity.value[self as usize - Field::VelocityX as usize] += delta;
+                }
+            }
+            Field::AccelerationX | Field::AccelerationY | Field::AccelerationZ => {
+                if let Some(acceleration) = item.acceleration.as_deref_mut() {
+                    acceleration.value[self as usize - Field::AccelerationX as usize] += delta;
+                }
+            }
+            Field::ColliderRadius => {
+                if let Some(collider) = item.collider.as_deref_mut() {
+                    collider.radius = (collider.radius + delta).max(0.0);
+                }
+            }
+        }
+    }
+}
+
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+struct Inspected {
+    transform: Option<&'static mut Transform>,
+    velocity: Option<&'static mut Velocity>,
+    acceleration: Option<&'static mut Acceleration>,
+    collider: Option<&'static mut Collider>,
+}
+
+type InspectableFilter = Or<(With<Spaceship>, With<Asteroid>, With<SpaceshipMissile>)>;
+type FieldTextFilter = (Without<SelectedText>, Without<KindSummary>);
+type InspectorUiFilter = Or<(With<InspectorRoot>, With<InspectorButton>)>;
+type ChangedButtonFilter = (With<InspectorButton>, Changed<Interaction>);
+
+#[derive(Component, Debug, Clone, Copy)]
+enum InspectorButton {
+    Cycle { kind: Kind, step: i32 },
+    Nudge { field: Field, direction: f32 },
+}
+
+#[derive(Component, Debug)]
+struct InspectorRoot;
+
+#[derive(Component, Debug)]
+struct KindSummary(Kind);
+
+#[derive(Component, Debug)]
+struct SelectedText;
+
+#[derive(Component, Debug)]
+struct FieldValue(Field);
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InspectorState>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(Update, toggle_inspector)
+            .add_systems(
+                Update,
+                (
+                    handle_buttons,
+                    pick_entity,
+                    update_panel,
+                    draw_selection,
+                    color_buttons,
+                )
+                    .chain()
+                    .run_if(inspector_open),
+            );
+    }
+}
+
+fn inspector_open(state: Res<InspectorState>) -> bool {
+    state.open
+}
+
+fn text(value: impl Into<String>) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size: FONT_SIZE,
+            color: Color::WHITE,
+            ..default()
+        },
+    )
+}
+
+fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(6.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, button: InspectorButton) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(22.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|button| {
+            button.spawn(text(label));
+        });
+}
+
+fn spawn_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(0.0),
+                    top: Val::Px(40.0),
+                    width: Val::Px(PANEL_WIDTH),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            // Lets clicks on the panel be told apart from clicks in the 3D view.
+            Interaction::default(),
+            InspectorRoot,
+        ))
+        .with_children(|panel| {
+            panel.spawn(text(format!("Inspector ({TOGGLE_KEY:?} to close)")));
+            for kind in Kind::ALL {
+                panel.spawn(row()).with_children(|row| {
+                    spawn_button(row, "<", InspectorButton::Cycle { kind, step: -1 });
+                    spawn_button(row, ">", InspectorButton::Cycle { kind, step: 1 });
+                    row.spawn((text(""), KindSummary(kind)));
+                });
+            }
+            panel.spawn((text(""), SelectedText));
+            for field in Field::ALL {
+                panel.spawn(row()).with_children(|row| {
+                    spawn_button(
+                        row,
+                        "-",
+                        InspectorButton::Nudge {
+                            field,
+                            direction: -1.0,
+                        },
+                    );
+                    spawn_button(
+                        row,
+                        "+",
+                        InspectorButton::Nudge {
+                            field,
+                            direction: 1.0,
+                        },
+                    );
+                    row.spawn((text(""), FieldValue(field)));
+                });
+            }
+        });
+}
+
+fn toggle_inspector(
+    mut state: ResMut<InspectorState>,
+    mut root_query: Query<&mut Visibility, With<InspectorRoot>>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if !keyboard_input.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    state.open = !state.open;
+    for mut visibility in root_query.iter_mut() {
+        *visibility = if state.open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn entities_of(
+    kind: Kind,
+    spaceship_query: &Query<Entity, With<Spaceship>>,
+    asteroid_query: &Query<Entity, With<Asteroid>>,
+    missile_query: &Query<Entity, With<SpaceshipMissile>>,
+) -> Vec<Entity> {
+    let mut entities: Vec<Entity> = match kind {
+        Kind::Spaceship => spaceship_query.iter().collect(),
+        Kind::Asteroid => asteroid_query.iter().collect(),
+        Kind::Missile => missile_query.iter().collect(),
+    };
+    entities.sort();
+    entities
+}
+
+fn handle_buttons(
+    mut state: ResMut<InspectorState>,
+    button_query: Query<(&Interaction, &InspectorButton), Changed<Interaction>>,
+    mut inspected_query: Query<Inspected>,
+    spaceship_query: Query<Entity, With<Spaceship>>,
+    asteroid_query: Query<Entity, With<Asteroid>>,
+    missile_query: Query<Entity, With<SpaceshipMissile>>,
+) {
+    for (interaction, button) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            InspectorButton::Cycle { kind, step } => {
+                let entities = entities_of(kind, &spaceship_query, &asteroid_query, &missile_query);
+                if entities.is_empty() {
+                    continue;
+                }
+                let len = entities.len() as i32;
+                let next = match state
+                    .selected
+                    .and_then(|selected| entities.iter().position(|&entity| entity == selected))
+                {
+                    Some(index) => (index as i32 + step).rem_euclid(len),
+                    None if step > 0 => 0,
+                    None => len - 1,
+                };
+                state.selected = Some(entities[next as usize]);
+            }
+            InspectorButton::Nudge { field, direction } => {
+                let Some(selected) = state.selected else {
+                    continue;
+                };
+                if let Ok(mut item) = inspected_query.get_mut(selected) {
+                    field.nudge(&mut item, direction);
+                }
+            }
+        }
+    }
+}
+
+fn pick_entity(
+    mut state: ResMut<InspectorState>,
+    mouse_input: Res<Input<MouseButton>>,
+    cursor_position: Res<CursorPosition>,
+    ui_query: Query<&Interaction, InspectorUiFilter>,
+    candidate_query: Query<(Entity, &GlobalTransform, &Collider), InspectableFilter>,
+) {
+    // The buttons block the panel's own interaction, so clicks on either are left to the UI.
+    if !mouse_input.just_pressed(MouseButton::Left)
+        || ui_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    let Some(point) = cursor_position.world else {
+        return;
+    };
+
+    state.selected = candidate_query
+        .iter()
+        .map(|(entity, transform, collider)| {
+            let distance = transform.translation().distance(point);
+            (entity, distance - collider.radius)
+        })
+        .filter(|&(_, outside_by)| outside_by <= 0.0)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_panel(
+    mut state: ResMut<InspectorState>,
+    mut inspected_query: Query<Inspected>,
+    mut summary_query: Query<(&mut Text, &KindSummary)>,
+    mut selected_query: Query<&mut Text, (With<SelectedText>, Without<KindSummary>)>,
+    mut field_query: Query<(&mut Text, &FieldValue), FieldTextFilter>,
+    spaceship_query: Query<Entity, With<Spaceship>>,
+    asteroid_query: Query<Entity, With<Asteroid>>,
+    missile_query: Query<Entity, With<SpaceshipMissile>>,
+) {
+    for (mut text, summary) in summary_query.iter_mut() {
+        let entities = entities_of(summary.0, &spaceship_query, &asteroid_query, &missile_query);
+        let mut listed: Vec<String> = entities
+            .iter()
+            .take(LISTED_PER_KIND)
+            .map(|entity| format!("{entity:?}"))
+            .collect();
+        if entities.len() > LISTED_PER_KIND {
+            listed.push(format!("+{}", entities.len() - LISTED_PER_KIND));
+        }
+        text.sections[0].value = format!(
+            "{} ({}): {}",
+            summary.0.label(),
+            entities.len(),
+            listed.join(" ")
+        );
+    }
+
+    // The selected entity may have been despawned since the last frame.
+    if state
+        .selected
+        .is_some_and(|selected| !inspected_query.contains(selected))
+    {
+        state.selected = None;
+    }
+    let item = state
+        .selected
+        .and_then(|selected| inspected_query.get_mut(selected).ok());
+
+    for mut text in selected_query.iter_mut() {
+        text.sections[0].value = match state.selected {
+            Some(selected) => format!("Selected: {selected:?}"),
+            None => "Selected: none (click an object or use < >)".into(),
+        };
+    }
+    for (mut text, field_value) in field_query.iter_mut() {
+        let value = item.as_ref().and_then(|item| field_value.0.value(item));
+        text.sections[0].value = match value {
+            Some(value) => format!("{:<16}{value:>9.2}", field_value.0.label()),
+            None => format!("{:<16}{:>9}", field_value.0.label(), "-"),
+        };
+    }
+}
+
+fn draw_selection(
+    mut gizmos: Gizmos,
+    state: Res<InspectorState>,
+    query: Query<(&GlobalTransform, Option<&Collider>)>,
+) {
+    let Some(Ok((transform, collider))) = state.selected.map(|selected| query.get(selected)) else {
+        return;
+    };
+    let radius = collider.map_or(1.0, |collider| collider.radius) + 0.5;
+    gizmos.circle(transform.translation(), Vec3::Y, radius, SELECTION_COLOR);
+}
+
+fn color_buttons(mut query: Query<(&Interaction, &mut BackgroundColor), ChangedButtonFilter>) {
+    for (interaction, mut color) in query.iter_mut() {
+        *color = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+        }
+        .into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn clicking_a_button_keeps_the_selection() {
+        let mut world = World::new();
+        let selected = world.spawn_empty().id();
+        world.insert_resource(InspectorState {
+            open: true,
+            selected: Some(selected),
+        });
+        let mut mouse_input = Input::<MouseButton>::default();
+        mouse_input.press(MouseButton::Left);
+        world.insert_resource(mouse_input);
+        world.insert_resource(CursorPosition {
+            world: Some(Vec3::ZERO),
+        });
+        world.spawn((InspectorRoot, Interaction::None));
+        let button = world
+            .spawn((
+                InspectorButton::Nudge {
+                    field: Field::Scale,
+                    direction: 1.0,
+                },
+                Interaction::Pressed,
+            ))
+            .id();
+
+        world.run_system_once(pick_entity);
+        assert_eq!(world.resource::<InspectorState>().selected, Some(selected));
+
+        // The same click on empty space clears it.
+        *world.get_mut::<Interaction>(button).unwrap() = Interaction::None;
+        world.run_system_once(pick_entity);
+        assert_eq!(world.resource::<InspectorState>().selected, None);
+    }
+}
@@ -0,0 +1,96 @@
+pub mod action;
+pub mod asset_loader;
+pub mod asteroid_mesh;
+pub mod asteroids;
+pub mod boss;
+pub mod camera;
+pub mod collision_detection;
+pub mod config;
+pub mod console;
+pub mod debug;
+pub mod despawn;
+pub mod health;
+pub mod hyperspace;
+pub mod inspector;
+pub mod manifest;
+pub mod movement;
+pub mod net;
+pub mod pool;
+pub mod powerup;
+pub mod radar;
+pub mod replay;
+pub mod rng;
+pub mod rollback;
+pub mod ron_file;
+pub mod save;
+pub mod score;
+pub mod sector;
+pub mod settings;
+pub mod shield;
+pub mod spaceship;
+pub mod state;
+pub mod steering;
+pub mod telemetry;
+pub mod ufo;
+pub mod wave;
+
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+use action::ActionPlugin;
+use asset_loader::AssetLoaderPlugin;
+use asteroids::AsteroidPlugin;
+use boss::BossPlugin;
+use camera::CameraPlugin;
+use collision_detection::CollisionDetectionPlugin;
+use config::ConfigPlugin;
+use console::ConsolePlugin;
+use debug::DebugPlugin;
+use despawn::DespawnPlugin;
+use hyperspace::HyperspacePlugin;
+use inspector::InspectorPlugin;
+use movement::MovementPlugin;
+use pool::PoolPlugin;
+use powerup::PowerUpPlugin;
+use radar::RadarPlugin;
+use rng::RngPlugin;
+use save::SavePlugin;
+use score::ScorePlugin;
+use sector::SectorPlugin;
+use shield::ShieldPlugin;
+use spaceship::SpaceshipPlugin;
+use state::StatePlugin;
+use ufo::UfoPlugin;
+use wave::WavePlugin;
+
+pub struct GamePlugin;
+
+impl PluginGroup for GamePlugin {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(ConfigPlugin)
+            .add(RngPlugin)
+            .add(StatePlugin)
+            .add(ConsolePlugin)
+            .add(ActionPlugin)
+            .add(AssetLoaderPlugin)
+            .add(MovementPlugin)
+            .add(SpaceshipPlugin)
+            .add(AsteroidPlugin)
+            .add(UfoPlugin)
+            .add(BossPlugin)
+            .add(PowerUpPlugin)
+            .add(ShieldPlugin)
+            .add(HyperspacePlugin)
+            .add(WavePlugin)
+            .add(SectorPlugin)
+            .add(CameraPlugin)
+            .add(RadarPlugin)
+            .add(CollisionDetectionPlugin)
+            .add(DespawnPlugin)
+            .add(PoolPlugin)
+            .add(ScorePlugin)
+            .add(SavePlugin)
+            .add(DebugPlugin)
+            .add(InspectorPlugin)
+    }
+}
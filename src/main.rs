@@ -1,39 +1,186 @@
-mod asset_loader;
-mod asteroids;
-mod camera;
-mod collision_detection;
-mod debug;
-mod despawn;
-mod movement;
-mod spaceship;
-
-use asset_loader::AssetLoaderPlugin;
-use asteroids::AsteroidPlugin;
-use bevy::prelude::*;
-use camera::CameraPlugin;
-use collision_detection::CollisionDetectionPlugin;
-use debug::DebugPlugin;
-use despawn::DespawnPlugin;
-use movement::MovementPlugin;
-use spaceship::SpaceshipPlugin;
+use std::{net::SocketAddr, path::PathBuf, process, time::Duration};
+
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    prelude::*,
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::{ExitCondition, WindowMode},
+    winit::WinitPlugin,
+};
+use bevy_game::{
+    config::GameConfig,
+    debug::DebugOverlay,
+    net::{NetClient, NetPlugin, NetServer},
+    replay::{Replay, ReplayPlugin},
+    rng::GameRng,
+    rollback::{PeerLink, RollbackPlugin, RollbackSession},
+    settings::{Settings, SettingsFile, DEFAULT_SETTINGS_PATH},
+    telemetry::TelemetryPlugin,
+    wave::Wave,
+    GamePlugin,
+};
+use clap::Parser;
+
+const HEADLESS_FRAME_SECONDS: f64 = 1.0 / 60.0;
+
+/// Asteroids-style shooter built with Bevy.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// Window width in logical pixels.
+    #[arg(long)]
+    width: Option<f32>,
+    /// Window height in logical pixels.
+    #[arg(long)]
+    height: Option<f32>,
+    /// Start in borderless fullscreen.
+    #[arg(long)]
+    fullscreen: bool,
+    /// Seed for the gameplay RNG. A random seed is used when omitted.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Wave to start the run at.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    wave: u32,
+    /// Show the debug overlay from the start. It can be toggled in game with F3.
+    #[arg(long)]
+    debug: bool,
+    /// Run the simulation without a window or renderer.
+    #[arg(long)]
+    headless: bool,
+    /// Play back a replay file. Its seed and starting wave take precedence.
+    #[arg(long, value_name = "FILE", conflicts_with = "record")]
+    replay: Option<PathBuf>,
+    /// Record this run's inputs to a replay file on exit.
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+    /// Append gameplay events as JSON lines to a file, see the `telemetry_report` binary.
+    #[arg(long, value_name = "FILE")]
+    telemetry: Option<PathBuf>,
+    /// Load game settings from a RON file.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Player settings such as input bindings, written back when changed from the console.
+    #[arg(long, value_name = "FILE", default_value = DEFAULT_SETTINGS_PATH)]
+    settings: PathBuf,
+    /// Host a LAN game on this UDP port, clients take over the `players` ships as they join.
+    #[arg(long, value_name = "PORT", conflicts_with = "connect")]
+    serve: Option<u16>,
+    /// Join a LAN game hosted with `--serve`, e.g. 127.0.0.1:7777.
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["replay", "record"])]
+    connect: Option<SocketAddr>,
+    /// Play a rollback versus match against the peer at this address. Both sides need the
+    /// same `--seed`.
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["serve", "connect", "replay", "record"])]
+    versus: Option<SocketAddr>,
+    /// Local UDP port for `--versus`.
+    #[arg(long, default_value_t = 7777)]
+    port: u16,
+    /// Which ship to fly in `--versus`, the peer takes the other one.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=2))]
+    player: u32,
+}
 
 fn main() {
-    App::new()
-        // Bevy built-ins.
-        .insert_resource(ClearColor(Color::rgb(0.1, 0.0, 0.15)))
-        .insert_resource(AmbientLight {
-            color: Color::default(),
-            brightness: 0.75,
-        })
-        .add_plugins(DefaultPlugins)
-        // User defined plugins.
-        .add_plugins(AssetLoaderPlugin)
-        .add_plugins(MovementPlugin)
-        .add_plugins(SpaceshipPlugin)
-        .add_plugins(AsteroidPlugin)
-        .add_plugins(CameraPlugin)
-        .add_plugins(CollisionDetectionPlugin)
-        .add_plugins(DespawnPlugin)
-        // .add_plugins(DebugPlugin)
-        .run();
+    let cli = Cli::parse();
+
+    let config = match &cli.config {
+        Some(path) => GameConfig::load(path).unwrap_or_else(|err| exit_with(&err)),
+        None => GameConfig::default(),
+    };
+    let settings = Settings::load_or_default(&cli.settings).unwrap_or_else(|err| exit_with(&err));
+    let replay = cli
+        .replay
+        .as_ref()
+        .map(|path| Replay::load(path).unwrap_or_else(|err| exit_with(&err)));
+
+    let (seed, wave) = match &replay {
+        Some(replay) => (replay.seed, replay.starting_wave),
+        // Versus peers simulate the same match, so they can't each pick a random seed.
+        None if cli.versus.is_some() => (cli.seed.unwrap_or_default(), cli.wave),
+        None => (cli.seed.unwrap_or_else(rand::random), cli.wave),
+    };
+
+    let mut app = App::new();
+    // Bevy built-ins.
+    if cli.headless {
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                })
+                .disable::<WinitPlugin>(),
+        )
+        .add_plugins(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            HEADLESS_FRAME_SECONDS,
+        )));
+    } else {
+        let mode = if cli.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+        app.add_plugins(
+            DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Bevy game".into(),
+                    resolution: (
+                        cli.width.unwrap_or(config.window_width),
+                        cli.height.unwrap_or(config.window_height),
+                    )
+                        .into(),
+                    mode,
+                    ..default()
+                }),
+                ..default()
+            }),
+        );
+    }
+
+    // User defined plugins.
+    app.insert_resource(config)
+        .insert_resource(settings)
+        .insert_resource(SettingsFile(cli.settings))
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(Wave::new(wave))
+        .insert_resource(DebugOverlay { enabled: cli.debug })
+        .add_plugins(GamePlugin);
+
+    if let Some(path) = cli.telemetry {
+        app.add_plugins(TelemetryPlugin { path });
+    }
+    if let Some(port) = cli.serve {
+        let server = NetServer::bind(port).unwrap_or_else(|err| exit_with(&err));
+        app.insert_resource(server).add_plugins(NetPlugin::Server);
+    } else if let Some(address) = cli.connect {
+        let client = NetClient::connect(address).unwrap_or_else(|err| exit_with(&err));
+        app.insert_resource(client).add_plugins(NetPlugin::Client);
+    } else if let Some(peer) = cli.versus {
+        let link = PeerLink::bind(cli.port, peer).unwrap_or_else(|err| exit_with(&err));
+        app.insert_resource(link)
+            .insert_resource(RollbackSession::new(cli.player as usize - 1))
+            .add_plugins(RollbackPlugin);
+    }
+    if let Some(replay) = replay {
+        app.add_plugins(ReplayPlugin::Playback(replay));
+    } else if let Some(path) = cli.record {
+        app.add_plugins(ReplayPlugin::Record { path, seed, wave });
+    }
+
+    info!("Starting run with seed {seed} at wave {wave}");
+    app.run();
+}
+
+fn exit_with(err: &dyn std::error::Error) -> ! {
+    eprintln!("error: {err}");
+    process::exit(1);
 }
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::ron_file::RonError;
+
+pub const MANIFEST_PATH: &str = "game.manifest.ron";
+
+// Maps the logical names the game asks for to files in `assets/`. A scene name can list
+// several variants, which are picked between at spawn time.
+#[derive(Asset, TypePath, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct AssetManifest {
+    pub scenes: HashMap<String, Vec<String>>,
+    pub sounds: HashMap<String, String>,
+    pub textures: HashMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct ManifestLoader;
+
+impl AssetLoader for ManifestLoader {
+    type Asset = AssetManifest;
+    type Settings = ();
+    type Error = RonError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<AssetManifest, RonError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["manifest.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shipped_manifest() {
+        let text = include_str!("../assets/game.manifest.ron");
+        let manifest: AssetManifest = ron::from_str(text).unwrap();
+        for name in ["spaceship", "missile", "asteroid"] {
+            assert!(!manifest.scenes[name].is_empty(), "no scene for {name}");
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use bevy::prelude::*;
 
-use crate::collision_detection::Collider;
+use crate::{collision_detection::Collider, rollback::Rollback};
 
-#[derive(Component, Debug)]
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component, Default)]
 pub struct Velocity {
     pub value: Vec3,
 }
@@ -13,7 +14,8 @@ impl Velocity {
     }
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component, Default)]
 pub struct Acceleration {
     pub value: Vec3,
 }
@@ -36,17 +38,26 @@ pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (update_velocity, update_position));
+        app.register_type::<Velocity>()
+            .register_type::<Acceleration>()
+            .add_systems(Update, (update_velocity, update_position));
     }
 }
 
-fn update_velocity(mut query: Query<(&Acceleration, &mut Velocity)>, time: Res<Time>) {
+// Rolled back entities move in fixed ticks instead, see `rollback`.
+fn update_velocity(
+    mut query: Query<(&Acceleration, &mut Velocity), Without<Rollback>>,
+    time: Res<Time>,
+) {
     for (acceleration, mut velocity) in query.iter_mut() {
         velocity.value += acceleration.value * time.delta_seconds();
     }
 }
 
-fn update_position(mut query: Query<(&Velocity, &mut Transform)>, time: Res<Time>) {
+fn update_position(
+    mut query: Query<(&Velocity, &mut Transform), Without<Rollback>>,
+    time: Res<Time>,
+) {
     for (velocity, mut transform) in query.iter_mut() {
         transform.translation += velocity.value * time.delta_seconds();
     }
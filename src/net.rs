@@ -0,0 +1,847 @@
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    net::{SocketAddr, UdpSocket},
+};
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    action::{Action, ActionState, ActionSystem, PlayerActions},
+    asset_loader::GameAssets,
+    asteroids::{Asteroid, ProceduralAsteroids, ASTEROID_SCENE},
+    config::GameConfig,
+    movement::Velocity,
+    ron_file::RonError,
+    score::Score,
+    spaceship::{
+        steer_spaceship, Lives, Player, Spaceship, SpaceshipMissile, MISSILE_SCENE, SPACESHIP_SCENE,
+    },
+    state::{GameState, StartState},
+};
+
+// The most a UDP datagram can carry.
+const MAX_DATAGRAM: usize = 65_507;
+const JOIN_RETRY_SECONDS: f32 = 1.0;
+// Clients that haven't been heard from for this long lose their ship to whoever joins next.
+const CLIENT_TIMEOUT_SECONDS: f32 = 5.0;
+// About two seconds of unacknowledged input at 60 frames per second.
+const MAX_PENDING_INPUTS: usize = 120;
+// Snapshots are sent in parts of this many entities, each well under `MAX_DATAGRAM` and
+// small enough that losing one doesn't lose much.
+const ENTITIES_PER_PART: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    Join,
+    // `sequence` goes up by one every frame so the server can say which input it has applied.
+    Input {
+        sequence: u32,
+        actions: Vec<(Action, f32)>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    Welcome { player: usize },
+    Full,
+    Snapshot(Snapshot),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub tick: u64,
+    // Which of the tick's `parts` this is, every part carries some of the entities.
+    pub part: u32,
+    pub parts: u32,
+    // The newest input of the receiving client that the server has applied.
+    pub acked: u32,
+    pub scores: Vec<u32>,
+    pub lives: Vec<u32>,
+    pub entities: Vec<NetEntity>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetEntity {
+    // The server's entity, only used to match up snapshots.
+    pub id: u64,
+    pub kind: NetKind,
+    pub transform: Transform,
+    pub velocity: Vec3,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum NetKind {
+    Spaceship {
+        player: usize,
+    },
+    Asteroid {
+        size: f32,
+        variant: usize,
+        procedural: bool,
+    },
+    Missile {
+        owner: usize,
+    },
+}
+
+impl Snapshot {
+    // Splits the entities over as many snapshots as it takes to keep each one small.
+    pub fn split(mut self) -> Vec<Snapshot> {
+        let entities = std::mem::take(&mut self.entities);
+        let chunks: Vec<&[NetEntity]> = if entities.is_empty() {
+            vec![&[]]
+        } else {
+            entities.chunks(ENTITIES_PER_PART).collect()
+        };
+        let parts = chunks.len() as u32;
+        chunks
+            .into_iter()
+            .zip(0..)
+            .map(|(entities, part)| Snapshot {
+                part,
+                parts,
+                entities: entities.to_vec(),
+                ..self.clone()
+            })
+            .collect()
+    }
+}
+
+// Collects the parts of the newest snapshot, a tick that loses a part is skipped as a whole.
+#[derive(Debug, Default)]
+struct SnapshotAssembly {
+    parts: Vec<Snapshot>,
+}
+
+impl SnapshotAssembly {
+    // The whole snapshot, once `part` was the last one missing.
+    fn add(&mut self, part: Snapshot) -> Option<Snapshot> {
+        match self.parts.first().map(|first| first.tick) {
+            Some(tick) if part.tick < tick => return None,
+            Some(tick) if part.tick > tick => self.parts.clear(),
+            _ => {}
+        }
+        if self.parts.iter().any(|other| other.part == part.part) {
+            return None;
+        }
+        self.parts.push(part);
+        if self.parts.len() < self.parts[0].parts as usize {
+            return None;
+        }
+        let mut parts = std::mem::take(&mut self.parts);
+        parts.sort_by_key(|part| part.part);
+        let mut parts = parts.into_iter();
+        let mut whole = parts.next()?;
+        for part in parts {
+            whole.entities.extend(part.entities);
+        }
+        whole.parts = 1;
+        Some(whole)
+    }
+}
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    Message(RonError),
+    TooLarge(usize),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(err) => write!(f, "network error: {err}"),
+            NetError::Message(err) => write!(f, "bad message: {err}"),
+            NetError::TooLarge(size) => write!(
+                f,
+                "message of {size} bytes does not fit in a datagram of {MAX_DATAGRAM}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(err: io::Error) -> Self {
+        NetError::Io(err)
+    }
+}
+
+impl From<RonError> for NetError {
+    fn from(err: RonError) -> Self {
+        NetError::Message(err)
+    }
+}
+
+pub fn encode<T: Serialize>(message: &T) -> Result<Vec<u8>, NetError> {
+    let text = ron::to_string(message).map_err(RonError::from)?;
+    if text.len() > MAX_DATAGRAM {
+        return Err(NetError::TooLarge(text.len()));
+    }
+    Ok(text.into_bytes())
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, NetError> {
+    Ok(ron::de::from_bytes(bytes).map_err(RonError::from)?)
+}
+
+pub(crate) fn open_socket(address: SocketAddr) -> Result<UdpSocket, NetError> {
+    let socket = UdpSocket::bind(address)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+pub(crate) fn send<T: Serialize>(socket: &UdpSocket, address: SocketAddr, message: &T) {
+    let result = encode(message).and_then(|bytes| Ok(socket.send_to(&bytes, address)?));
+    if let Err(err) = result {
+        warn!("Failed to send to {address}: {err}");
+    }
+}
+
+// The next well formed message, or `None` once there is nothing left to read this frame.
+pub(crate) fn receive<T: DeserializeOwned>(socket: &UdpSocket) -> Option<(SocketAddr, T)> {
+    let mut buffer = vec![0; MAX_DATAGRAM];
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((size, address)) => match decode(&buffer[..size]) {
+                Ok(message) => return Some((address, message)),
+                Err(err) => warn!("Dropped a packet from {address}: {err}"),
+            },
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => return None,
+            // Refused connections show up here on some platforms, nothing to do but wait.
+            Err(err) => {
+                debug!("Failed to receive: {err}");
+                return None;
+            }
+        }
+    }
+}
+
+// The authoritative side, runs the game and hands ships to clients as they join.
+#[derive(Resource, Debug)]
+pub struct NetServer {
+    socket: UdpSocket,
+    clients: Vec<RemoteClient>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct RemoteClient {
+    address: SocketAddr,
+    player: usize,
+    sequence: u32,
+    input: Vec<(Action, f32)>,
+    actions: ActionState,
+    last_heard: f32,
+}
+
+impl NetServer {
+    pub fn bind(port: u16) -> Result<Self, NetError> {
+        Ok(Self {
+            socket: open_socket(SocketAddr::from(([0, 0, 0, 0], port)))?,
+            clients: vec![],
+            tick: 0,
+        })
+    }
+
+    pub fn port(&self) -> Result<u16, NetError> {
+        Ok(self.socket.local_addr()?.port())
+    }
+}
+
+// Shows the server's game and predicts the local ship until the server catches up.
+#[derive(Resource, Debug)]
+pub struct NetClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+    // Handed out by the server once it has accepted us.
+    player: Option<usize>,
+    join_timer: f32,
+    sequence: u32,
+    pending: VecDeque<PendingInput>,
+    last_tick: u64,
+    assembly: SnapshotAssembly,
+    // Server entity ids to their local copies.
+    entities: HashMap<u64, Entity>,
+}
+
+impl NetClient {
+    pub fn connect(server: SocketAddr) -> Result<Self, NetError> {
+        let local = if server.is_ipv4() {
+            SocketAddr::from(([0, 0, 0, 0], 0))
+        } else {
+            SocketAddr::from(([0u16; 8], 0))
+        };
+        Ok(Self {
+            socket: open_socket(local)?,
+            server,
+            player: None,
+            join_timer: 0.0,
+            sequence: 0,
+            pending: VecDeque::new(),
+            last_tick: 0,
+            assembly: SnapshotAssembly::default(),
+            entities: HashMap::default(),
+        })
+    }
+}
+
+// Input the server has not acknowledged yet, with the frame time it was applied for.
+#[derive(Debug, Clone)]
+pub struct PendingInput {
+    pub sequence: u32,
+    pub actions: ActionState,
+    pub delta: f32,
+}
+
+// Marks the local copy of an entity that lives on the server.
+#[derive(Component, Debug)]
+struct Replica;
+
+// The `NetServer` or `NetClient` resource has to be inserted along with the plugin, so that
+// failing to open the socket can be reported before the app starts.
+pub enum NetPlugin {
+    Server,
+    Client,
+}
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        match self {
+            NetPlugin::Server => {
+                app.add_systems(PreUpdate, receive_inputs.after(ActionSystem))
+                    .add_systems(PostUpdate, send_snapshots);
+            }
+            NetPlugin::Client => {
+                app.insert_resource(StartState(GameState::Remote))
+                    .add_systems(
+                        PreUpdate,
+                        (receive_snapshots, send_input)
+                            .chain()
+                            .after(ActionSystem)
+                            .run_if(in_state(GameState::Remote)),
+                    )
+                    .add_systems(
+                        Update,
+                        predict_local_ship.run_if(in_state(GameState::Remote)),
+                    );
+            }
+        }
+    }
+}
+
+// Remote players' inputs replace whatever local input their player has.
+fn receive_inputs(
+    mut server: ResMut<NetServer>,
+    mut player_actions: ResMut<PlayerActions>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let server = &mut *server;
+    let now = time.elapsed_seconds();
+    while let Some((address, message)) = receive::<ClientMessage>(&server.socket) {
+        let known = server
+            .clients
+            .iter_mut()
+            .find(|client| client.address == address);
+        match (message, known) {
+            (ClientMessage::Join, Some(client)) => {
+                let welcome = ServerMessage::Welcome {
+                    player: client.player,
+                };
+                send(&server.socket, address, &welcome);
+            }
+            (ClientMessage::Join, None) => {
+                let free = (0..config.players.max(1) as usize)
+                    .find(|&player| server.clients.iter().all(|client| client.player != player));
+                let Some(player) = free else {
+                    send(&server.socket, address, &ServerMessage::Full);
+                    continue;
+                };
+                info!("Player {} joined from {address}", player + 1);
+                server.clients.push(RemoteClient {
+                    address,
+                    player,
+                    sequence: 0,
+                    input: vec![],
+                    actions: ActionState::default(),
+                    last_heard: now,
+                });
+                send(&server.socket, address, &ServerMessage::Welcome { player });
+            }
+            (ClientMessage::Input { sequence, actions }, Some(client)) => {
+                client.last_heard = now;
+                // Datagrams can arrive out of order, an older input is already out of date.
+                if sequence > client.sequence {
+                    client.sequence = sequence;
+                    client.input = actions;
+                }
+            }
+            (ClientMessage::Input { .. }, None) => {}
+        }
+    }
+
+    server.clients.retain(|client| {
+        let alive = now - client.last_heard < CLIENT_TIMEOUT_SECONDS;
+        if !alive {
+            info!("Player {} timed out", client.player + 1);
+        }
+        alive
+    });
+    for client in server.clients.iter_mut() {
+        client.actions.update(&client.input);
+        player_actions.set_player(client.player, &client.actions);
+    }
+}
+
+type ReplicatedShip<'a> = (Entity, &'a Player, &'a Transform, &'a Velocity);
+type ReplicatedAsteroid<'a> = (Entity, &'a Asteroid, &'a Transform, &'a Velocity);
+type ReplicatedMissile<'a> = (Entity, &'a SpaceshipMissile, &'a Transform, &'a Velocity);
+
+fn send_snapshots(
+    mut server: ResMut<NetServer>,
+    spaceship_query: Query<ReplicatedShip, With<Spaceship>>,
+    asteroid_query: Query<ReplicatedAsteroid>,
+    missile_query: Query<ReplicatedMissile>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+) {
+    server.tick += 1;
+    if server.clients.is_empty() {
+        return;
+    }
+    let net_entity = |entity: Entity, kind, transform: &Transform, velocity: &Velocity| NetEntity {
+        id: entity.to_bits(),
+        kind,
+        transform: *transform,
+        velocity: velocity.value,
+    };
+    let ships = spaceship_query
+        .iter()
+        .map(|(entity, player, transform, velocity)| {
+            let kind = NetKind::Spaceship { player: player.id };
+            net_entity(entity, kind, transform, velocity)
+        });
+    let asteroids = asteroid_query
+        .iter()
+        .map(|(entity, asteroid, transform, velocity)| {
+            let kind = NetKind::Asteroid {
+                size: asteroid.size,
+                variant: asteroid.variant,
+                procedural: asteroid.procedural,
+            };
+            net_entity(entity, kind, transform, velocity)
+        });
+    let missiles = missile_query
+        .iter()
+        .map(|(entity, missile, transform, velocity)| {
+            let kind = NetKind::Missile {
+                owner: missile.owner,
+            };
+            net_entity(entity, kind, transform, velocity)
+        });
+    let mut parts = Snapshot {
+        tick: server.tick,
+        part: 0,
+        parts: 1,
+        acked: 0,
+        scores: score.players.clone(),
+        lives: lives.remaining.clone(),
+        entities: ships.chain(asteroids).chain(missiles).collect(),
+    }
+    .split();
+    for client in server.clients.iter() {
+        for part in parts.iter_mut() {
+            part.acked = client.sequence;
+            send(
+                &server.socket,
+                client.address,
+                &ServerMessage::Snapshot(part.clone()),
+            );
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn receive_snapshots(
+    mut commands: Commands,
+    mut client: ResMut<NetClient>,
+    mut replica_query: Query<(&mut Transform, &mut Velocity), With<Replica>>,
+    game_assets: Res<GameAssets>,
+    procedural: Res<ProceduralAsteroids>,
+    mut score: ResMut<Score>,
+    mut lives: ResMut<Lives>,
+    time: Res<Time>,
+) {
+    let client = &mut *client;
+    let mut newest: Option<Snapshot> = None;
+    while let Some((address, message)) = receive::<ServerMessage>(&client.socket) {
+        if address != client.server {
+            continue;
+        }
+        match message {
+            ServerMessage::Welcome { player } => {
+                if client.player.is_none() {
+                    info!("Joined {} as player {}", client.server, player + 1);
+                }
+                client.player = Some(player);
+            }
+            ServerMessage::Full => warn!("{} has no free ship", client.server),
+            ServerMessage::Snapshot(part) => {
+                let tick = newest
+                    .as_ref()
+                    .map_or(client.last_tick, |newest| newest.tick);
+                if part.tick <= tick {
+                    continue;
+                }
+                if let Some(snapshot) = client.assembly.add(part) {
+                    newest = Some(snapshot);
+                }
+            }
+        }
+    }
+    if client.player.is_none() {
+        client.join_timer -= time.delta_seconds();
+        if client.join_timer <= 0.0 {
+            client.join_timer = JOIN_RETRY_SECONDS;
+            send(&client.socket, client.server, &ClientMessage::Join);
+        }
+    }
+    let Some(snapshot) = newest else {
+        return;
+    };
+
+    client.last_tick = snapshot.tick;
+    client
+        .pending
+        .retain(|input| input.sequence > snapshot.acked);
+    if score.players != snapshot.scores {
+        score.players = snapshot.scores;
+    }
+    if lives.remaining != snapshot.lives {
+        lives.remaining = snapshot.lives;
+    }
+
+    let mut seen = HashSet::default();
+    for entity in snapshot.entities {
+        seen.insert(entity.id);
+        let mut transform = entity.transform;
+        let mut velocity = entity.velocity;
+        if matches!(entity.kind, NetKind::Spaceship { player } if client.player == Some(player)) {
+            (transform, velocity) = predict(transform, velocity, client.pending.iter());
+        }
+        let replica = client
+            .entities
+            .get(&entity.id)
+            .and_then(|&replica| replica_query.get_mut(replica).ok());
+        match replica {
+            Some((mut replica_transform, mut replica_velocity)) => {
+                *replica_transform = transform;
+                replica_velocity.value = velocity;
+            }
+            None => {
+                let replica = spawn_replica(
+                    &mut commands,
+                    &game_assets,
+                    &procedural,
+                    entity.kind,
+                    transform,
+                    velocity,
+                );
+                client.entities.insert(entity.id, replica);
+            }
+        }
+    }
+    // Whatever the server stopped sending is gone.
+    client.entities.retain(|id, &mut replica| {
+        let keep = seen.contains(id);
+        if !keep {
+            if let Some(replica) = commands.get_entity(replica) {
+                replica.despawn_recursive();
+            }
+        }
+        keep
+    });
+}
+
+fn spawn_replica(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    procedural: &ProceduralAsteroids,
+    kind: NetKind,
+    transform: Transform,
+    velocity: Vec3,
+) -> Entity {
+    let scene = match kind {
+        NetKind::Spaceship { .. } => game_assets.scene(SPACESHIP_SCENE),
+        // The client's procedural meshes come from its own seed, so only the variant matches.
+        NetKind::Asteroid {
+            variant,
+            procedural: true,
+            ..
+        } if variant < procedural.variants.len() => procedural.variants[variant].scene.clone(),
+        NetKind::Asteroid { variant, .. } => game_assets.scene_variant(ASTEROID_SCENE, variant),
+        NetKind::Missile { .. } => game_assets.scene(MISSILE_SCENE),
+    };
+    let mut replica = commands.spawn((
+        SceneBundle {
+            scene,
+            transform,
+            ..default()
+        },
+        Velocity::new(velocity),
+        Replica,
+    ));
+    match kind {
+        NetKind::Spaceship { player } => {
+            replica.insert((Spaceship, Player { id: player }));
+        }
+        NetKind::Asteroid {
+            size,
+            variant,
+            procedural,
+        } => {
+            replica.insert(Asteroid {
+                size,
+                variant,
+                procedural,
+            });
+        }
+        NetKind::Missile { owner } => {
+            replica.insert(SpaceshipMissile { owner });
+        }
+    }
+    replica.id()
+}
+
+// Clients always play with the first player's bindings.
+fn send_input(mut client: ResMut<NetClient>, player_actions: Res<PlayerActions>, time: Res<Time>) {
+    if client.player.is_none() {
+        return;
+    }
+    let actions = player_actions.get(0);
+    client.sequence += 1;
+    let sequence = client.sequence;
+    let message = ClientMessage::Input {
+        sequence,
+        actions: actions.active(),
+    };
+    send(&client.socket, client.server, &message);
+    client.pending.push_back(PendingInput {
+        sequence,
+        actions: actions.clone(),
+        delta: time.delta_seconds(),
+    });
+    if client.pending.len() > MAX_PENDING_INPUTS {
+        client.pending.pop_front();
+    }
+}
+
+// The local ship reacts straight away instead of waiting for the server's next snapshot.
+fn predict_local_ship(
+    client: Res<NetClient>,
+    mut query: Query<(&Player, &mut Transform, &mut Velocity), With<Spaceship>>,
+    player_actions: Res<PlayerActions>,
+    time: Res<Time>,
+) {
+    let Some(local) = client.player else {
+        return;
+    };
+    for (player, mut transform, mut velocity) in query.iter_mut() {
+        if player.id == local {
+            velocity.value =
+                steer_spaceship(&mut transform, player_actions.get(0), time.delta_seconds());
+        }
+    }
+}
+
+// Starts from where the server last saw the ship and applies the inputs it hasn't seen yet.
+pub fn predict<'a>(
+    mut transform: Transform,
+    mut velocity: Vec3,
+    pending: impl IntoIterator<Item = &'a PendingInput>,
+) -> (Transform, Vec3) {
+    for input in pending {
+        velocity = steer_spaceship(&mut transform, &input.actions, input.delta);
+        transform.translation += velocity * input.delta;
+    }
+    (transform, velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    fn wait_for<T: DeserializeOwned>(socket: &UdpSocket) -> (SocketAddr, T) {
+        for _ in 0..200 {
+            if let Some(received) = receive(socket) {
+                return received;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("nothing arrived");
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let snapshot = ServerMessage::Snapshot(Snapshot {
+            tick: 12,
+            part: 0,
+            parts: 1,
+            acked: 7,
+            scores: vec![100, 200],
+            lives: vec![3, 1],
+            entities: vec![NetEntity {
+                id: 42,
+                kind: NetKind::Missile { owner: 1 },
+                transform: Transform::from_xyz(1.0, 0.0, -2.0),
+                velocity: Vec3::Z,
+            }],
+        });
+        let decoded: ServerMessage = decode(&encode(&snapshot).unwrap()).unwrap();
+        assert_eq!(decoded, snapshot);
+    }
+
+    #[test]
+    fn snapshots_are_split_and_put_back_together() {
+        let entity = |id| NetEntity {
+            id,
+            kind: NetKind::Missile { owner: 0 },
+            transform: Transform::default(),
+            velocity: Vec3::ZERO,
+        };
+        let snapshot = |tick, count| Snapshot {
+            tick,
+            part: 0,
+            parts: 1,
+            acked: 0,
+            scores: vec![],
+            lives: vec![],
+            entities: (0..count).map(entity).collect(),
+        };
+        let parts = snapshot(5, 3 * ENTITIES_PER_PART as u64).split();
+        assert_eq!(parts.len(), 3);
+        for part in parts.iter() {
+            assert!(encode(&ServerMessage::Snapshot(part.clone())).is_ok());
+        }
+
+        let mut assembly = SnapshotAssembly::default();
+        // A newer tick drops the incomplete older one.
+        assert_eq!(assembly.add(parts[2].clone()), None);
+        let newer = snapshot(6, 2 * ENTITIES_PER_PART as u64).split();
+        assert_eq!(assembly.add(newer[1].clone()), None);
+        assert_eq!(assembly.add(parts[0].clone()), None);
+        assert_eq!(assembly.add(newer[1].clone()), None);
+        let whole = assembly.add(newer[0].clone()).unwrap();
+        assert_eq!(whole, snapshot(6, 2 * ENTITIES_PER_PART as u64));
+    }
+
+    #[test]
+    fn client_app_replicates_a_server_app() {
+        let mut server_app = App::new();
+        server_app
+            .add_plugins(MinimalPlugins)
+            .init_resource::<PlayerActions>()
+            .init_resource::<GameConfig>()
+            .init_resource::<Score>()
+            .init_resource::<Lives>()
+            .insert_resource(NetServer::bind(0).unwrap())
+            .add_plugins(NetPlugin::Server);
+        let port = server_app.world.resource::<NetServer>().port().unwrap();
+        server_app.world.spawn((
+            Spaceship,
+            Player { id: 0 },
+            Transform::default(),
+            Velocity::new(Vec3::ZERO),
+        ));
+        // More asteroids than fit in one datagram.
+        for i in 0..400 {
+            server_app.world.spawn((
+                Asteroid::default(),
+                Transform::from_xyz(i as f32, 0.0, -(i as f32)),
+                Velocity::new(Vec3::X),
+            ));
+        }
+
+        let mut client_app = App::new();
+        client_app
+            .add_plugins(MinimalPlugins)
+            .add_state::<GameState>()
+            .init_resource::<PlayerActions>()
+            .init_resource::<GameAssets>()
+            .init_resource::<ProceduralAsteroids>()
+            .init_resource::<Score>()
+            .init_resource::<Lives>()
+            .insert_resource(NetClient::connect(SocketAddr::from(([127, 0, 0, 1], port))).unwrap())
+            .insert_resource(NextState(Some(GameState::Remote)))
+            .add_plugins(NetPlugin::Client);
+
+        let replicas = |app: &mut App| {
+            app.world
+                .query_filtered::<(), With<Replica>>()
+                .iter(&app.world)
+                .count()
+        };
+        for _ in 0..200 {
+            client_app.update();
+            server_app.update();
+            if replicas(&mut client_app) == 401 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(client_app.world.resource::<NetClient>().player, Some(0));
+        assert_eq!(replicas(&mut client_app), 401);
+        let ship = client_app
+            .world
+            .query_filtered::<&Player, (With<Replica>, With<Spaceship>)>()
+            .single(&client_app.world);
+        assert_eq!(ship.id, 0);
+    }
+
+    #[test]
+    fn client_joins_a_server_on_localhost() {
+        let server = NetServer::bind(0).unwrap();
+        let address = SocketAddr::from(([127, 0, 0, 1], server.port().unwrap()));
+        let client = NetClient::connect(address).unwrap();
+
+        send(&client.socket, address, &ClientMessage::Join);
+        let (client_address, message) = wait_for::<ClientMessage>(&server.socket);
+        assert_eq!(message, ClientMessage::Join);
+
+        send(
+            &server.socket,
+            client_address,
+            &ServerMessage::Welcome { player: 1 },
+        );
+        let (_, message) = wait_for::<ServerMessage>(&client.socket);
+        assert_eq!(message, ServerMessage::Welcome { player: 1 });
+    }
+
+    #[test]
+    fn prediction_replays_unacknowledged_input() {
+        let mut thrust = ActionState::default();
+        thrust.update(&[(Action::Thrust, 1.0)]);
+        let pending: Vec<PendingInput> = (1..=3)
+            .map(|sequence| PendingInput {
+                sequence,
+                actions: thrust.clone(),
+                delta: 0.1,
+            })
+            .collect();
+
+        let (idle, _) = predict(Transform::default(), Vec3::ZERO, &[]);
+        let (moved, velocity) = predict(Transform::default(), Vec3::ZERO, &pending);
+        assert_eq!(idle.translation, Vec3::ZERO);
+        assert!(velocity.length() > 0.0);
+        assert!((moved.translation - velocity * 0.3).length() < 1e-4);
+    }
+}
@@ -0,0 +1,298 @@
+use bevy::{
+    ecs::{system::Command, world::EntityWorldMut},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::console::ConsoleAppExt;
+
+// Retiring more of one scene than this despawns the extra entities instead of keeping them.
+const MAX_FREE_PER_SCENE: usize = 256;
+
+// Marks an entity spawned through the pool. Retiring it strips the gameplay bundle it was
+// spawned with and hides it, keeping the scene hierarchy around for the next spawn.
+#[derive(Component, Debug)]
+pub struct Pooled {
+    active: bool,
+    strip: fn(&mut EntityWorldMut),
+}
+
+impl Pooled {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct EntityPool {
+    free: HashMap<AssetId<Scene>, Vec<Entity>>,
+    // Strips components other plugins may have added since the spawn, see `reset_on_retire`.
+    resets: Vec<fn(&mut EntityWorldMut)>,
+    pub hits: u32,
+    pub misses: u32,
+}
+
+impl EntityPool {
+    pub fn free_count(&self) -> usize {
+        self.free.values().map(Vec::len).sum()
+    }
+}
+
+pub struct SpawnPooled<B: Bundle> {
+    pub scene: Handle<Scene>,
+    pub transform: Transform,
+    pub bundle: B,
+}
+
+impl<B: Bundle> SpawnPooled<B> {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let scene_id = self.scene.id();
+        while let Some(entity) = world
+            .resource_mut::<EntityPool>()
+            .free
+            .get_mut(&scene_id)
+            .and_then(Vec::pop)
+        {
+            let Some(mut entity_mut) = world.get_entity_mut(entity) else {
+                continue;
+            };
+            entity_mut.insert((
+                self.transform,
+                GlobalTransform::from(self.transform),
+                Visibility::Inherited,
+                self.bundle,
+            ));
+            // The scene may have been pooled with another bundle, retiring strips this one now.
+            if let Some(mut pooled) = entity_mut.get_mut::<Pooled>() {
+                pooled.active = true;
+                pooled.strip = strip::<B>;
+            }
+            world.resource_mut::<EntityPool>().hits += 1;
+            return entity;
+        }
+
+        world.resource_mut::<EntityPool>().misses += 1;
+        world
+            .spawn((
+                SceneBundle {
+                    scene: self.scene,
+                    transform: self.transform,
+                    global_transform: GlobalTransform::from(self.transform),
+                    ..default()
+                },
+                Pooled {
+                    active: true,
+                    strip: strip::<B>,
+                },
+                self.bundle,
+            ))
+            .id()
+    }
+}
+
+impl<B: Bundle> Command for SpawnPooled<B> {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
+    }
+}
+
+fn strip<B: Bundle>(entity: &mut EntityWorldMut) {
+    entity.remove::<B>();
+}
+
+// Despawns entities that didn't come from the pool, or that would overflow its free list.
+pub struct Retire(pub Entity);
+
+impl Command for Retire {
+    fn apply(self, world: &mut World) {
+        let Some(entity_ref) = world.get_entity(self.0) else {
+            return;
+        };
+        let Some(&Pooled { active, strip }) = entity_ref.get::<Pooled>() else {
+            world.entity_mut(self.0).despawn_recursive();
+            return;
+        };
+        // Several systems can retire the same entity in one frame.
+        if !active {
+            return;
+        }
+        let Some(scene_id) = entity_ref.get::<Handle<Scene>>().map(Handle::id) else {
+            world.entity_mut(self.0).despawn_recursive();
+            return;
+        };
+        let mut pool = world.resource_mut::<EntityPool>();
+        let free = pool.free.entry(scene_id).or_default();
+        if free.len() >= MAX_FREE_PER_SCENE {
+            world.entity_mut(self.0).despawn_recursive();
+            return;
+        }
+        free.push(self.0);
+        let resets = pool.resets.clone();
+
+        let mut entity_mut = world.entity_mut(self.0);
+        strip(&mut entity_mut);
+        for reset in resets {
+            reset(&mut entity_mut);
+        }
+        entity_mut.insert((Transform::default(), Visibility::Hidden));
+        entity_mut.get_mut::<Pooled>().unwrap().active = false;
+    }
+}
+
+pub trait PoolCommandsExt {
+    fn spawn_pooled<B: Bundle>(&mut self, spawn: SpawnPooled<B>);
+    fn retire(&mut self, entity: Entity);
+}
+
+impl PoolCommandsExt for Commands<'_, '_> {
+    fn spawn_pooled<B: Bundle>(&mut self, spawn: SpawnPooled<B>) {
+        self.add(spawn);
+    }
+
+    fn retire(&mut self, entity: Entity) {
+        self.add(Retire(entity));
+    }
+}
+
+pub trait PoolAppExt {
+    // Components that can end up on a pooled entity besides the bundle it was spawned with.
+    fn reset_on_retire<B: Bundle>(&mut self) -> &mut Self;
+}
+
+impl PoolAppExt for App {
+    fn reset_on_retire<B: Bundle>(&mut self) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(EntityPool::default)
+            .resets
+            .push(strip::<B>);
+        self
+    }
+}
+
+pub struct PoolPlugin;
+
+impl Plugin for PoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityPool>().add_console_command(
+            "pool",
+            "show entity pool hits, misses and free entities",
+            pool_command,
+        );
+    }
+}
+
+fn pool_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let pool = world.resource::<EntityPool>();
+    Ok(format!(
+        "hits: {}, misses: {}, free: {}",
+        pool.hits,
+        pool.misses,
+        pool.free_count()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Marker;
+
+    fn spawn_marker(world: &mut World, scene: &Handle<Scene>) -> Entity {
+        SpawnPooled {
+            scene: scene.clone(),
+            transform: Transform::from_xyz(1.0, 0.0, 2.0),
+            bundle: Marker,
+        }
+        .spawn(world)
+    }
+
+    #[test]
+    fn retired_entities_are_reused() {
+        let mut world = World::new();
+        world.init_resource::<EntityPool>();
+        let scene = Handle::weak_from_u128(1);
+
+        let first = spawn_marker(&mut world, &scene);
+        Retire(first).apply(&mut world);
+        Retire(first).apply(&mut world);
+        assert!(!world.entity(first).contains::<Marker>());
+        assert_eq!(world.entity(first).get(), Some(&Visibility::Hidden));
+
+        let second = spawn_marker(&mut world, &scene);
+        assert_eq!(first, second);
+        assert!(world.entity(second).contains::<Marker>());
+        assert_eq!(world.entity(second).get(), Some(&Visibility::Inherited));
+
+        let pool = world.resource::<EntityPool>();
+        assert_eq!((pool.hits, pool.misses, pool.free_count()), (1, 1, 0));
+    }
+
+    #[test]
+    fn reused_entities_strip_the_bundle_they_were_reused_with() {
+        #[derive(Component)]
+        struct Extra;
+
+        let mut world = World::new();
+        world.init_resource::<EntityPool>();
+        let scene = Handle::weak_from_u128(1);
+
+        let first = spawn_marker(&mut world, &scene);
+        Retire(first).apply(&mut world);
+        let second = SpawnPooled {
+            scene: scene.clone(),
+            transform: Transform::default(),
+            bundle: (Marker, Extra),
+        }
+        .spawn(&mut world);
+        assert_eq!(first, second);
+
+        Retire(second).apply(&mut world);
+        assert!(!world.entity(second).contains::<Marker>());
+        assert!(!world.entity(second).contains::<Extra>());
+    }
+
+    #[test]
+    fn retiring_strips_components_added_since_the_spawn() {
+        #[derive(Component)]
+        struct Extra;
+
+        let mut app = App::new();
+        app.init_resource::<EntityPool>().reset_on_retire::<Extra>();
+        let world = &mut app.world;
+        let scene = Handle::weak_from_u128(1);
+
+        let entity = spawn_marker(world, &scene);
+        world.entity_mut(entity).insert(Extra);
+        Retire(entity).apply(world);
+        assert!(!world.entity(entity).contains::<Extra>());
+    }
+
+    #[test]
+    fn free_lists_are_capped() {
+        let mut world = World::new();
+        world.init_resource::<EntityPool>();
+        let scene = Handle::weak_from_u128(1);
+
+        let entities: Vec<Entity> = (0..MAX_FREE_PER_SCENE + 1)
+            .map(|_| spawn_marker(&mut world, &scene))
+            .collect();
+        for &entity in entities.iter() {
+            Retire(entity).apply(&mut world);
+        }
+        assert_eq!(
+            world.resource::<EntityPool>().free_count(),
+            MAX_FREE_PER_SCENE
+        );
+        assert!(world.get_entity(entities[MAX_FREE_PER_SCENE]).is_none());
+    }
+
+    #[test]
+    fn unpooled_entities_are_despawned() {
+        let mut world = World::new();
+        world.init_resource::<EntityPool>();
+        let entity = world.spawn(Marker).id();
+        Retire(entity).apply(&mut world);
+        assert!(world.get_entity(entity).is_none());
+    }
+}
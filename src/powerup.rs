@@ -0,0 +1,405 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    asteroids::Asteroid,
+    collision_detection::{apply_collision_damage, Collider, CollisionEvent},
+    config::GameConfig,
+    health::{DamageEvent, Invulnerable},
+    movement::{Acceleration, Velocity},
+    pool::{PoolCommandsExt, SpawnPooled},
+    rng::GameRng,
+    spaceship::{Lives, Player, Spaceship, SpaceshipMissile},
+    state::GameState,
+};
+
+const RADIUS: f32 = 1.5;
+const DRIFT_SPEED: f32 = 2.0;
+const LIFETIME_SECONDS: f32 = 12.0;
+const SPIN_SPEED: f32 = 2.0;
+const SHIELD_SECONDS: f32 = 8.0;
+const RAPID_FIRE_SECONDS: f32 = 10.0;
+const MULTI_SHOT_SECONDS: f32 = 10.0;
+const SCORE_MULTIPLIER_SECONDS: f32 = 15.0;
+pub const SCORE_MULTIPLIER: u32 = 2;
+const FONT_SIZE: f32 = 20.0;
+
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+    #[default]
+    Shield,
+    RapidFire,
+    MultiShot,
+    ExtraLife,
+    ScoreMultiplier,
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::Shield,
+        PowerUpKind::RapidFire,
+        PowerUpKind::MultiShot,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::ScoreMultiplier,
+    ];
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUpKind::Shield => Color::rgb(0.3, 0.6, 1.0),
+            PowerUpKind::RapidFire => Color::rgb(1.0, 0.5, 0.1),
+            PowerUpKind::MultiShot => Color::rgb(0.9, 0.2, 0.9),
+            PowerUpKind::ExtraLife => Color::rgb(0.2, 1.0, 0.3),
+            PowerUpKind::ScoreMultiplier => Color::rgb(1.0, 0.9, 0.2),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "Shield",
+            PowerUpKind::RapidFire => "Rapid fire",
+            PowerUpKind::MultiShot => "Multi-shot",
+            PowerUpKind::ExtraLife => "Extra life",
+            PowerUpKind::ScoreMultiplier => "Score x2",
+        }
+    }
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    lifetime: Timer,
+}
+
+impl PowerUp {
+    pub fn new(kind: PowerUpKind) -> Self {
+        Self {
+            kind,
+            lifetime: Timer::from_seconds(LIFETIME_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+impl Default for PowerUp {
+    fn default() -> Self {
+        Self::new(PowerUpKind::default())
+    }
+}
+
+// Seconds left on each timed effect of a spaceship.
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component, Default)]
+pub struct PowerUpEffects {
+    pub shield: f32,
+    pub rapid_fire: f32,
+    pub multi_shot: f32,
+    pub score_multiplier: f32,
+}
+
+impl PowerUpEffects {
+    fn timers(&self) -> [(PowerUpKind, f32); 4] {
+        [
+            (PowerUpKind::Shield, self.shield),
+            (PowerUpKind::RapidFire, self.rapid_fire),
+            (PowerUpKind::MultiShot, self.multi_shot),
+            (PowerUpKind::ScoreMultiplier, self.score_multiplier),
+        ]
+    }
+}
+
+// The ship's `Invulnerable` came from a shield, so it goes again when the shield runs out.
+#[derive(Component, Debug, Default)]
+pub struct ShieldInvulnerable;
+
+#[derive(Resource, Debug, Default)]
+struct PowerUpAssets {
+    scenes: Vec<(PowerUpKind, Handle<Scene>)>,
+}
+
+impl PowerUpAssets {
+    fn scene(&self, kind: PowerUpKind) -> Handle<Scene> {
+        self.scenes
+            .iter()
+            .find(|(scene_kind, _)| *scene_kind == kind)
+            .map(|(_, scene)| scene.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Component, Debug)]
+struct PowerUpHud;
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PowerUp>()
+            .register_type::<PowerUpEffects>()
+            .init_resource::<PowerUpAssets>()
+            .add_systems(Startup, (build_powerup_assets, spawn_hud))
+            .add_systems(
+                Update,
+                (
+                    drop_powerups.after(apply_collision_damage),
+                    collect_powerups.after(apply_collision_damage),
+                    expire_powerups,
+                    tick_effects,
+                )
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Update, update_hud)
+            .add_systems(OnEnter(GameState::GameOver), clear_powerups);
+    }
+}
+
+fn build_powerup_assets(
+    mut powerup_assets: ResMut<PowerUpAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut scenes: ResMut<Assets<Scene>>,
+) {
+    let mesh = meshes.add(Mesh::from(shape::Cube { size: RADIUS }));
+    for kind in PowerUpKind::ALL {
+        let mut world = World::new();
+        world.spawn(PbrBundle {
+            mesh: mesh.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: kind.color(),
+                emissive: kind.color() * 0.6,
+                ..default()
+            }),
+            ..default()
+        });
+        powerup_assets
+            .scenes
+            .push((kind, scenes.add(Scene::new(world))));
+    }
+}
+
+// Asteroids shot down by the spaceship sometimes leave a power-up behind.
+fn drop_powerups(
+    mut commands: Commands,
+    mut damage_event_reader: EventReader<DamageEvent>,
+    asteroid_query: Query<&Transform, With<Asteroid>>,
+    missile_query: Query<(), With<SpaceshipMissile>>,
+    powerup_assets: Res<PowerUpAssets>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+) {
+    for event in damage_event_reader.read() {
+        let killed = event.health_left <= 0.0 && event.health_left + event.amount > 0.0;
+        if !killed || !missile_query.contains(event.source) {
+            continue;
+        }
+        let Ok(transform) = asteroid_query.get(event.entity) else {
+            continue;
+        };
+        if !rng.gen_bool(config.powerup_drop_chance.clamp(0.0, 1.0) as f64) {
+            continue;
+        }
+
+        let kind = PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())];
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        commands.spawn_pooled(SpawnPooled {
+            scene: powerup_assets.scene(kind),
+            transform: Transform::from_translation(transform.translation),
+            bundle: (
+                Velocity::new(Vec3::new(angle.cos(), 0.0, angle.sin()) * DRIFT_SPEED),
+                Acceleration::new(Vec3::ZERO),
+                Collider::new(RADIUS),
+                PowerUp::new(kind),
+            ),
+        });
+    }
+}
+
+fn collect_powerups(
+    mut commands: Commands,
+    mut collision_event_reader: EventReader<CollisionEvent>,
+    mut spaceship_query: Query<(&Player, &mut PowerUpEffects), With<Spaceship>>,
+    powerup_query: Query<&PowerUp>,
+    mut lives: ResMut<Lives>,
+) {
+    for &CollisionEvent {
+        entity,
+        collided_entity,
+    } in collision_event_reader.read()
+    {
+        let Ok((player, mut effects)) = spaceship_query.get_mut(entity) else {
+            continue;
+        };
+        let Ok(powerup) = powerup_query.get(collided_entity) else {
+            continue;
+        };
+        match powerup.kind {
+            PowerUpKind::Shield => effects.shield = SHIELD_SECONDS,
+            PowerUpKind::RapidFire => effects.rapid_fire = RAPID_FIRE_SECONDS,
+            PowerUpKind::MultiShot => effects.multi_shot = MULTI_SHOT_SECONDS,
+            PowerUpKind::ExtraLife => {
+                if let Some(remaining) = lives.remaining.get_mut(player.id) {
+                    *remaining += 1;
+                }
+            }
+            PowerUpKind::ScoreMultiplier => effects.score_multiplier = SCORE_MULTIPLIER_SECONDS,
+        }
+        info!("Collected {}", powerup.kind.name());
+        commands.retire(collided_entity);
+    }
+}
+
+fn expire_powerups(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut PowerUp, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut powerup, mut transform) in query.iter_mut() {
+        transform.rotate_y(SPIN_SPEED * time.delta_seconds());
+        if powerup.lifetime.tick(time.delta()).just_finished() {
+            commands.retire(entity);
+        }
+    }
+}
+
+// The shield is the only effect other systems can't just read, it's applied through
+// `Invulnerable` while it lasts. A ship that was already invulnerable is left as it was.
+fn tick_effects(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut PowerUpEffects,
+        Has<Invulnerable>,
+        Has<ShieldInvulnerable>,
+    )>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut effects, invulnerable, from_shield) in query.iter_mut() {
+        effects.shield = (effects.shield - delta).max(0.0);
+        effects.rapid_fire = (effects.rapid_fire - delta).max(0.0);
+        effects.multi_shot = (effects.multi_shot - delta).max(0.0);
+        effects.score_multiplier = (effects.score_multiplier - delta).max(0.0);
+
+        if effects.shield > 0.0 && !invulnerable {
+            commands
+                .entity(entity)
+                .insert((Invulnerable, ShieldInvulnerable));
+        } else if effects.shield <= 0.0 && from_shield {
+            commands
+                .entity(entity)
+                .remove::<(Invulnerable, ShieldInvulnerable)>();
+        }
+    }
+}
+
+fn clear_powerups(mut commands: Commands, query: Query<Entity, With<PowerUp>>) {
+    for entity in query.iter() {
+        commands.retire(entity);
+    }
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::default().with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        PowerUpHud,
+    ));
+}
+
+// One line per running effect, prefixed with the player once there is more than one ship.
+fn update_hud(
+    spaceship_query: Query<(&Player, &PowerUpEffects), With<Spaceship>>,
+    mut hud_query: Query<&mut Text, With<PowerUpHud>>,
+) {
+    let mut ships: Vec<(&Player, &PowerUpEffects)> = spaceship_query.iter().collect();
+    ships.sort_by_key(|(player, _)| player.id);
+    let sections: Vec<TextSection> = ships
+        .iter()
+        .flat_map(|(player, effects)| {
+            let prefix = if ships.len() > 1 {
+                format!("P{} ", player.id + 1)
+            } else {
+                String::new()
+            };
+            effects
+                .timers()
+                .into_iter()
+                .filter(|&(_, seconds)| seconds > 0.0)
+                .map(move |(kind, seconds)| {
+                    TextSection::new(
+                        format!("{prefix}{} {seconds:.1}s\n", kind.name()),
+                        TextStyle {
+                            font_size: FONT_SIZE,
+                            color: kind.color(),
+                            ..default()
+                        },
+                    )
+                })
+        })
+        .collect();
+    for mut text in hud_query.iter_mut() {
+        text.sections = sections.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn shield_keeps_the_ship_invulnerable_until_it_runs_out() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let ship = world
+            .spawn(PowerUpEffects {
+                shield: 0.5,
+                rapid_fire: 0.2,
+                ..default()
+            })
+            .id();
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.3));
+        world.run_system_once(tick_effects);
+        let effects = world.get::<PowerUpEffects>(ship).unwrap();
+        assert_eq!(effects.rapid_fire, 0.0);
+        assert!(world.entity(ship).contains::<Invulnerable>());
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.3));
+        world.run_system_once(tick_effects);
+        assert_eq!(world.get::<PowerUpEffects>(ship).unwrap().shield, 0.0);
+        assert!(!world.entity(ship).contains::<Invulnerable>());
+    }
+
+    #[test]
+    fn shield_running_out_keeps_god_mode() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let ship = world
+            .spawn((
+                PowerUpEffects {
+                    shield: 0.2,
+                    ..default()
+                },
+                Invulnerable,
+            ))
+            .id();
+
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(0.3));
+        world.run_system_once(tick_effects);
+        assert_eq!(world.get::<PowerUpEffects>(ship).unwrap().shield, 0.0);
+        assert!(world.entity(ship).contains::<Invulnerable>());
+    }
+}
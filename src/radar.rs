@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::save_settings,
+    asteroids::Asteroid,
+    boss::Boss,
+    camera::GameCamera,
+    collision_detection::Collider,
+    console::ConsoleAppExt,
+    powerup::PowerUp,
+    settings::Settings,
+    spaceship::{Player, Spaceship},
+    ufo::Ufo,
+};
+
+const RADAR_SIZE: f32 = 160.0;
+const RADAR_BACKGROUND: Color = Color::rgba(0.0, 0.3, 0.1, 0.35);
+const SHIP_BLIP_SIZE: f32 = 6.0;
+const BLIP_SIZE: f32 = 5.0;
+// Asteroid blips grow with the asteroid, in pixels per unit of collider radius.
+const BLIP_PER_RADIUS: f32 = 2.0;
+const MAX_BLIP_SIZE: f32 = 14.0;
+const ASTEROID_COLOR: Color = Color::GRAY;
+const ENEMY_COLOR: Color = Color::RED;
+const MARKER_SIZE: f32 = 10.0;
+const MARKER_MARGIN: f32 = 12.0;
+
+#[derive(Component, Debug)]
+struct Radar;
+
+#[derive(Component, Debug, Clone, Copy)]
+struct RadarBlip;
+
+// Points from the edge of the screen towards a threat outside the view.
+#[derive(Component, Debug, Clone, Copy)]
+struct ThreatMarker;
+
+// A dot to draw, centred on `position` in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mark {
+    position: Vec2,
+    size: f32,
+    color: Color,
+}
+
+type Contact<'a> = (
+    &'a GlobalTransform,
+    Option<&'a Collider>,
+    Option<&'a PowerUp>,
+    Has<Asteroid>,
+    Has<Boss>,
+);
+type ContactFilter = Or<(With<Asteroid>, With<Ufo>, With<Boss>, With<PowerUp>)>;
+type RadarFilter = (With<Radar>, Without<RadarBlip>);
+type MarkNode<'a> = (
+    Entity,
+    &'a mut Style,
+    &'a mut BackgroundColor,
+    &'a mut Visibility,
+);
+
+pub struct RadarPlugin;
+
+impl Plugin for RadarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_radar)
+            .add_systems(Update, (update_radar, update_threat_markers))
+            .add_console_command(
+                "radar",
+                "radar [range]: show or set how far the radar reaches, 0 hides it",
+                radar_command,
+            );
+    }
+}
+
+fn spawn_radar(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-RADAR_SIZE / 2.0)),
+                width: Val::Px(RADAR_SIZE),
+                height: Val::Px(RADAR_SIZE),
+                ..default()
+            },
+            background_color: RADAR_BACKGROUND.into(),
+            ..default()
+        },
+        Radar,
+    ));
+}
+
+// Colour and size of a contact's blip, enemies are whatever isn't an asteroid or a pickup.
+fn contact_blip(
+    collider: Option<&Collider>,
+    powerup: Option<&PowerUp>,
+    asteroid: bool,
+    boss: bool,
+) -> (Color, f32) {
+    if let Some(powerup) = powerup {
+        (powerup.kind.color(), BLIP_SIZE)
+    } else if asteroid {
+        let radius = collider.map_or(1.0, |collider| collider.radius);
+        let size = (radius * BLIP_PER_RADIUS).clamp(BLIP_SIZE / 2.0, MAX_BLIP_SIZE);
+        (ASTEROID_COLOR, size)
+    } else if boss {
+        (ENEMY_COLOR, MAX_BLIP_SIZE)
+    } else {
+        (ENEMY_COLOR, BLIP_SIZE)
+    }
+}
+
+// Where something `offset` away from the ship shows on the radar, laid out like the camera
+// sees it: world +Z is up the screen and +X is to the left.
+fn radar_position(offset: Vec3, range: f32) -> Option<Vec2> {
+    let flat = Vec2::new(-offset.x, -offset.z) / range;
+    (flat.abs().max_element() <= 1.0).then(|| (flat + Vec2::ONE) / 2.0 * RADAR_SIZE)
+}
+
+// Pulls a point outside the viewport in along the line from the centre until it sits
+// `margin` inside the edge. Points already on screen need no marker.
+fn edge_position(point: Vec2, viewport: Vec2, margin: f32) -> Option<Vec2> {
+    if point.cmpge(Vec2::ZERO).all() && point.cmple(viewport).all() {
+        return None;
+    }
+    let center = viewport / 2.0;
+    let direction = point - center;
+    let reach = (center - margin).max(Vec2::ZERO) / direction.abs().max(Vec2::splat(f32::EPSILON));
+    Some(center + direction * reach.min_element())
+}
+
+// Centres the radar on the first player's ship.
+fn update_radar(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut radar_query: Query<(Entity, &mut Visibility), RadarFilter>,
+    mut blip_query: Query<MarkNode, With<RadarBlip>>,
+    spaceship_query: Query<(&Player, &GlobalTransform), With<Spaceship>>,
+    contact_query: Query<Contact, ContactFilter>,
+) {
+    let Ok((radar, mut visibility)) = radar_query.get_single_mut() else {
+        return;
+    };
+    let range = settings.radar_range;
+    *visibility = if range > 0.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    let center = spaceship_query
+        .iter()
+        .min_by_key(|(player, _)| player.id)
+        .map(|(_, transform)| transform.translation());
+
+    let mut marks = vec![];
+    if let Some(center) = center.filter(|_| range > 0.0) {
+        for (transform, collider, powerup, asteroid, boss) in contact_query.iter() {
+            let Some(position) = radar_position(transform.translation() - center, range) else {
+                continue;
+            };
+            let (color, size) = contact_blip(collider, powerup, asteroid, boss);
+            marks.push(Mark {
+                position,
+                size,
+                color,
+            });
+        }
+        // Ships go last so they are drawn over everything else.
+        for (player, transform) in spaceship_query.iter() {
+            if let Some(position) = radar_position(transform.translation() - center, range) {
+                marks.push(Mark {
+                    position,
+                    size: SHIP_BLIP_SIZE,
+                    color: player.tint(),
+                });
+            }
+        }
+    }
+    show_marks(
+        &mut commands,
+        Some(radar),
+        RadarBlip,
+        &mut blip_query,
+        &marks,
+    );
+}
+
+// Asteroids and enemies the radar can see but the camera can't.
+fn update_threat_markers(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut marker_query: Query<MarkNode, With<ThreatMarker>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    spaceship_query: Query<(&Player, &GlobalTransform), With<Spaceship>>,
+    contact_query: Query<Contact, ContactFilter>,
+) {
+    let view = camera_query
+        .get_single()
+        .ok()
+        .and_then(|(camera, transform)| Some((camera, transform, camera.logical_viewport_size()?)));
+    let center = spaceship_query
+        .iter()
+        .min_by_key(|(player, _)| player.id)
+        .map(|(_, transform)| transform.translation());
+
+    let mut marks = vec![];
+    if let (Some((camera, camera_transform, viewport)), Some(center)) = (view, center) {
+        for (transform, collider, powerup, asteroid, boss) in contact_query.iter() {
+            let translation = transform.translation();
+            if powerup.is_some() || translation.distance(center) > settings.radar_range {
+                continue;
+            }
+            let Some(position) = camera
+                .world_to_viewport(camera_transform, translation)
+                .and_then(|point| edge_position(point, viewport, MARKER_MARGIN))
+            else {
+                continue;
+            };
+            marks.push(Mark {
+                position,
+                size: MARKER_SIZE,
+                color: contact_blip(collider, None, asteroid, boss).0,
+            });
+        }
+    }
+    show_marks(&mut commands, None, ThreatMarker, &mut marker_query, &marks);
+}
+
+// Moves the existing nodes onto `marks`, spawning more when there are not enough and hiding
+// the rest, so nodes are not respawned every frame.
+fn show_marks<M: Component + Copy>(
+    commands: &mut Commands,
+    parent: Option<Entity>,
+    marker: M,
+    query: &mut Query<MarkNode, With<M>>,
+    marks: &[Mark],
+) {
+    let mut nodes = query.iter_mut();
+    for mark in marks {
+        let left = Val::Px(mark.position.x - mark.size / 2.0);
+        let top = Val::Px(mark.position.y - mark.size / 2.0);
+        let size = Val::Px(mark.size);
+        if let Some((_, mut style, mut background, mut visibility)) = nodes.next() {
+            style.left = left;
+            style.top = top;
+            style.width = size;
+            style.height = size;
+            *background = mark.color.into();
+            *visibility = Visibility::Inherited;
+            continue;
+        }
+        let node = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left,
+                        top,
+                        width: size,
+                        height: size,
+                        ..default()
+                    },
+                    background_color: mark.color.into(),
+                    ..default()
+                },
+                marker,
+            ))
+            .id();
+        if let Some(parent) = parent {
+            commands.entity(parent).add_child(node);
+        }
+    }
+    for (_, _, _, mut visibility) in nodes {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn radar_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [range] = args else {
+        let range = world.resource::<Settings>().radar_range;
+        return Ok(format!("{range}"));
+    };
+    let range: f32 = range
+        .parse()
+        .ok()
+        .filter(|range: &f32| *range >= 0.0)
+        .ok_or_else(|| format!("'{range}' is not a valid range"))?;
+    world.resource_mut::<Settings>().radar_range = range;
+    save_settings(world)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radar_matches_the_camera_and_drops_what_is_out_of_range() {
+        let middle = Vec2::splat(RADAR_SIZE / 2.0);
+        assert_eq!(radar_position(Vec3::ZERO, 100.0), Some(middle));
+        // Ahead on +Z is up the radar, +X is to the left.
+        let ahead = radar_position(Vec3::new(0.0, 0.0, 50.0), 100.0).unwrap();
+        assert_eq!(ahead, middle - Vec2::new(0.0, RADAR_SIZE / 4.0));
+        let left = radar_position(Vec3::new(100.0, 0.0, 0.0), 100.0).unwrap();
+        assert_eq!(left, Vec2::new(0.0, RADAR_SIZE / 2.0));
+        assert_eq!(radar_position(Vec3::new(0.0, 0.0, -101.0), 100.0), None);
+    }
+
+    #[test]
+    fn threat_markers_sit_on_the_edge_towards_the_threat() {
+        let viewport = Vec2::new(800.0, 600.0);
+        assert_eq!(edge_position(Vec2::new(10.0, 20.0), viewport, 10.0), None);
+        assert_eq!(
+            edge_position(Vec2::new(2400.0, 300.0), viewport, 10.0),
+            Some(Vec2::new(790.0, 300.0))
+        );
+        let corner = edge_position(Vec2::new(-400.0, -300.0), viewport, 10.0).unwrap();
+        assert!((corner.y - 10.0).abs() < 1e-3 && corner.x > 10.0);
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{app::AppExit, input::InputSystem, prelude::*, time::TimeUpdateStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::{Action, ActionState, ActionSystem, PlayerActions},
+    camera::{update_cursor_position, CursorPosition},
+    ron_file::{self, RonError},
+    state::GameState,
+};
+
+// Input and frame time for every frame of a run. Together with the seed and starting wave this
+// is enough to play the run back.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub starting_wave: u32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayFrame {
+    pub delta: f32,
+    // Keys still drive the console, quicksaves and debug tools.
+    pub pressed: Vec<KeyCode>,
+    // Each player's actions, whether they came from keys, buttons, sticks or the mouse.
+    // Replays recorded before these were kept leave them empty and play back the keys instead.
+    #[serde(default)]
+    pub actions: Vec<Vec<(Action, f32)>>,
+    // Where the mouse pointed, for mouse aim.
+    #[serde(default)]
+    pub cursor: Option<Vec3>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, starting_wave: u32) -> Self {
+        Self {
+            seed,
+            starting_wave,
+            frames: vec![],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RonError> {
+        ron_file::read(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RonError> {
+        let text = ron::to_string(self)?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+pub enum ReplayPlugin {
+    Record { path: PathBuf, seed: u64, wave: u32 },
+    Playback(Replay),
+}
+
+#[derive(Resource, Debug)]
+struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+#[derive(Resource, Debug)]
+struct ReplayPlayback {
+    replay: Replay,
+    frame: usize,
+    players: Vec<ActionState>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match self {
+            ReplayPlugin::Record { path, seed, wave } => {
+                app.insert_resource(ReplayRecorder {
+                    path: path.clone(),
+                    replay: Replay::new(*seed, *wave),
+                })
+                .add_systems(
+                    PreUpdate,
+                    record_frame
+                        .after(ActionSystem)
+                        .after(update_cursor_position)
+                        .run_if(not(in_state(GameState::Loading))),
+                )
+                .add_systems(Last, save_recording);
+            }
+            ReplayPlugin::Playback(replay) => {
+                if let Some(first) = replay.frames.first() {
+                    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+                        Duration::from_secs_f32(first.delta),
+                    ));
+                }
+                app.insert_resource(ReplayPlayback {
+                    replay: replay.clone(),
+                    frame: 0,
+                    players: vec![],
+                })
+                .add_systems(
+                    PreUpdate,
+                    (
+                        play_keys.after(InputSystem).before(ActionSystem),
+                        play_actions
+                            .after(ActionSystem)
+                            .after(update_cursor_position),
+                    )
+                        .chain()
+                        .run_if(not(in_state(GameState::Loading))),
+                );
+            }
+        }
+    }
+}
+
+fn record_frame(
+    mut recorder: ResMut<ReplayRecorder>,
+    keyboard_input: Res<Input<KeyCode>>,
+    player_actions: Res<PlayerActions>,
+    cursor_position: Res<CursorPosition>,
+    time: Res<Time>,
+) {
+    recorder.replay.frames.push(ReplayFrame {
+        delta: time.delta_seconds(),
+        pressed: keyboard_input.get_pressed().copied().collect(),
+        actions: (0..player_actions.player_count())
+            .map(|player| player_actions.get(player).active())
+            .collect(),
+        cursor: cursor_position.world,
+    });
+}
+
+fn save_recording(recorder: Res<ReplayRecorder>, mut exit_events: EventReader<AppExit>) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    match recorder.replay.save(&recorder.path) {
+        Ok(()) => info!(
+            "Saved {} replay frames to {:?}",
+            recorder.replay.frames.len(),
+            recorder.path
+        ),
+        Err(err) => error!("Failed to save replay: {err}"),
+    }
+}
+
+fn play_keys(
+    playback: Res<ReplayPlayback>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    let Some(frame) = playback.replay.frames.get(playback.frame) else {
+        info!("Replay finished");
+        exit_events.send(AppExit);
+        return;
+    };
+
+    let released: Vec<KeyCode> = keyboard_input
+        .get_pressed()
+        .filter(|key| !frame.pressed.contains(key))
+        .copied()
+        .collect();
+    for key in released {
+        keyboard_input.release(key);
+    }
+    for &key in frame.pressed.iter() {
+        keyboard_input.press(key);
+    }
+}
+
+// Replaces whatever the live devices did with the recorded actions.
+fn play_actions(
+    mut playback: ResMut<ReplayPlayback>,
+    mut player_actions: ResMut<PlayerActions>,
+    mut cursor_position: ResMut<CursorPosition>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let playback = &mut *playback;
+    let Some(frame) = playback.replay.frames.get(playback.frame) else {
+        return;
+    };
+    if !frame.actions.is_empty() {
+        playback
+            .players
+            .resize_with(frame.actions.len(), ActionState::default);
+        for (player, (action_state, actions)) in
+            playback.players.iter_mut().zip(&frame.actions).enumerate()
+        {
+            action_state.update(actions);
+            player_actions.set_player(player, action_state);
+        }
+        cursor_position.world = frame.cursor;
+    }
+
+    // The time strategy is consumed at the start of the next frame.
+    playback.frame += 1;
+    if let Some(next) = playback.replay.frames.get(playback.frame) {
+        *time_strategy = TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(next.delta));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn recorded_actions_replace_live_input() {
+        let mut world = World::new();
+        world.init_resource::<PlayerActions>();
+        world.init_resource::<CursorPosition>();
+        world.init_resource::<TimeUpdateStrategy>();
+        let frame = |actions: Vec<(Action, f32)>| ReplayFrame {
+            delta: 0.1,
+            pressed: vec![],
+            actions: vec![vec![], actions],
+            cursor: Some(Vec3::X),
+        };
+        world.insert_resource(ReplayPlayback {
+            replay: Replay {
+                frames: vec![
+                    frame(vec![(Action::Fire, 1.0)]),
+                    frame(vec![(Action::Fire, 1.0)]),
+                ],
+                ..default()
+            },
+            frame: 0,
+            players: vec![],
+        });
+
+        world.run_system_once(play_actions);
+        let player_actions = world.resource::<PlayerActions>();
+        assert!(player_actions.get(1).just_pressed(Action::Fire));
+        assert!(!player_actions.get(0).pressed(Action::Fire));
+        assert_eq!(world.resource::<CursorPosition>().world, Some(Vec3::X));
+
+        world.run_system_once(play_actions);
+        let player_actions = world.resource::<PlayerActions>();
+        assert!(player_actions.get(1).pressed(Action::Fire));
+        assert!(!player_actions.get(1).just_pressed(Action::Fire));
+    }
+}
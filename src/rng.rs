@@ -0,0 +1,60 @@
+use std::ops::{Deref, DerefMut};
+
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// Every gameplay roll goes through this so a run can be reproduced from its seed.
+#[derive(Resource, Debug, Clone)]
+pub struct GameRng {
+    pub seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    // Rebuilds the generator at the point reached after `word_pos` words were drawn from it.
+    pub fn from_state(seed: u64, word_pos: u64) -> Self {
+        let mut rng = Self::new(seed);
+        rng.rng.set_word_pos(word_pos.into());
+        rng
+    }
+
+    pub fn word_pos(&self) -> u64 {
+        self.rng.get_word_pos() as u64
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl Deref for GameRng {
+    type Target = ChaCha8Rng;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rng
+    }
+}
+
+impl DerefMut for GameRng {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rng
+    }
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>();
+    }
+}
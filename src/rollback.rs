@@ -0,0 +1,710 @@
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    f32::consts::FRAC_PI_2,
+    hash::{Hash, Hasher},
+    net::{SocketAddr, UdpSocket},
+};
+
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::{Action, ActionState, PlayerActions},
+    asset_loader::GameAssets,
+    collision_detection::Collider,
+    hyperspace::find_safe_point,
+    movement::{Acceleration, Velocity},
+    net::{open_socket, receive, send, NetError},
+    rng::GameRng,
+    score::Score,
+    spaceship::{
+        steer_spaceship, Lives, Player, Spaceship, MISSILE_SCENE, SPACESHIP_RADIUS, SPACESHIP_SCENE,
+    },
+    state::{GameState, StartState},
+};
+
+const TICK_SECONDS: f32 = 1.0 / 60.0;
+// After a long frame the session catches up by at most this many ticks, dropping the rest of
+// the time so a slow machine doesn't fall further behind every frame.
+const MAX_CATCH_UP_TICKS: u32 = 4;
+// How many ticks the simulation may run ahead of the peer's last known input.
+pub const MAX_PREDICTION_TICKS: u32 = 8;
+// Enough snapshots to roll back over any prediction.
+const SNAPSHOT_HISTORY: usize = MAX_PREDICTION_TICKS as usize + 2;
+const VERSUS_LIVES: u32 = 3;
+// Missiles are never spawned mid-match, each ship has this many it can have in flight.
+const MISSILES_PER_SHIP: usize = 4;
+const MISSILE_SPEED: f32 = 50.0;
+const MISSILE_RADIUS: f32 = 1.0;
+const MISSILE_FORWARD_SPAWN_SCALAR: f32 = 7.5;
+// Ships stay inside this, missiles leaving it are spent.
+const ARENA: Vec3 = Vec3::new(45.0, 0.0, 28.0);
+const START_X: f32 = 25.0;
+// How far from the other ship a hit ship reappears.
+const RESPAWN_CLEARANCE: f32 = 20.0;
+
+// One fixed tick of the match. Several of these can run in a single frame when a late input
+// means the last few ticks have to be simulated again.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RollbackSchedule;
+
+// Entities whose state is saved every tick and restored on a rollback.
+#[derive(Component, Debug, Default)]
+pub struct Rollback;
+
+#[derive(Component, Debug)]
+pub struct VersusMissile {
+    pub owner: usize,
+    // Order in which free missiles are used, the same on both peers.
+    slot: usize,
+}
+
+// Which actions are held on one tick, packed to be cheap to send and compare.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InputFrame(u16);
+
+impl InputFrame {
+    pub fn from_actions(action_state: &ActionState) -> Self {
+        Self(
+            Action::ALL
+                .iter()
+                .enumerate()
+                .filter(|&(_, &action)| action_state.pressed(action))
+                .fold(0, |bits, (index, _)| bits | 1 << index),
+        )
+    }
+
+    pub fn pressed(self, action: Action) -> bool {
+        Action::ALL
+            .iter()
+            .position(|&other| other == action)
+            .is_some_and(|index| self.0 & 1 << index != 0)
+    }
+
+    // `previous` is the tick before, so that `just_pressed` works in the simulation.
+    fn action_state(self, previous: InputFrame) -> ActionState {
+        let mut action_state = ActionState::default();
+        action_state.update(&previous.values());
+        action_state.update(&self.values());
+        action_state
+    }
+
+    fn values(self) -> Vec<(Action, f32)> {
+        Action::ALL
+            .into_iter()
+            .filter(|&action| self.pressed(action))
+            .map(|action| (action, 1.0))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PeerMessage {
+    // How many of the receiver's ticks the sender has inputs for.
+    pub ack: u32,
+    // The sender's inputs from tick `start` on. They are sent again until acknowledged, so a
+    // lost packet only delays them.
+    pub start: u32,
+    pub inputs: Vec<InputFrame>,
+}
+
+// Both players' inputs for the tick being simulated.
+#[derive(Resource, Debug, Default)]
+pub struct RollbackInputs {
+    pub players: [ActionState; 2],
+}
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    tick: u32,
+    entities: Vec<(Entity, Transform, Velocity, Acceleration, Collider)>,
+    rng: GameRng,
+    lives: Lives,
+    score: Score,
+}
+
+type SavedState<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Velocity,
+    &'a Acceleration,
+    &'a Collider,
+);
+
+impl Snapshot {
+    fn save(world: &mut World, tick: u32) -> Self {
+        let entities = world
+            .query_filtered::<SavedState, With<Rollback>>()
+            .iter(world)
+            .map(|(entity, transform, velocity, acceleration, collider)| {
+                (
+                    entity,
+                    *transform,
+                    velocity.clone(),
+                    acceleration.clone(),
+                    collider.clone(),
+                )
+            })
+            .collect();
+        Self {
+            tick,
+            entities,
+            rng: world.resource::<GameRng>().clone(),
+            lives: world.resource::<Lives>().clone(),
+            score: world.resource::<Score>().clone(),
+        }
+    }
+
+    fn restore(&self, world: &mut World) {
+        for (entity, transform, velocity, acceleration, collider) in self.entities.iter() {
+            if let Some(mut entity_mut) = world.get_entity_mut(*entity) {
+                entity_mut.insert((
+                    *transform,
+                    velocity.clone(),
+                    acceleration.clone(),
+                    collider.clone(),
+                ));
+            }
+        }
+        world.insert_resource(self.rng.clone());
+        world.insert_resource(self.lives.clone());
+        world.insert_resource(self.score.clone());
+    }
+}
+
+// GGPO style: the remote player's input is guessed when it hasn't arrived yet, and when it
+// turns out different the match is rolled back to that tick and simulated again.
+#[derive(Resource, Debug)]
+pub struct RollbackSession {
+    local: usize,
+    // The next tick to simulate.
+    tick: u32,
+    // Every tick's input by player, the remote ones from `confirmed` on are guesses.
+    inputs: [Vec<InputFrame>; 2],
+    confirmed: u32,
+    // How many of our inputs the peer has.
+    peer_ack: u32,
+    rollback_from: Option<u32>,
+    snapshots: VecDeque<Snapshot>,
+    rollbacks: u32,
+}
+
+impl RollbackSession {
+    pub fn new(local: usize) -> Self {
+        Self {
+            local,
+            tick: 0,
+            inputs: [vec![], vec![]],
+            confirmed: 0,
+            peer_ack: 0,
+            rollback_from: None,
+            snapshots: VecDeque::new(),
+            rollbacks: 0,
+        }
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn rollbacks(&self) -> u32 {
+        self.rollbacks
+    }
+
+    fn remote(&self) -> usize {
+        1 - self.local
+    }
+
+    // Running further ahead of the peer would need longer rollbacks, so the faster side waits.
+    pub fn can_advance(&self) -> bool {
+        self.tick < self.confirmed + MAX_PREDICTION_TICKS
+    }
+
+    pub fn message(&self) -> PeerMessage {
+        let start = self.peer_ack.min(self.tick);
+        PeerMessage {
+            ack: self.confirmed,
+            start,
+            inputs: self.inputs[self.local][start as usize..].to_vec(),
+        }
+    }
+
+    pub fn receive(&mut self, message: &PeerMessage) {
+        self.peer_ack = self.peer_ack.max(message.ack);
+        let remote = self.remote();
+        for (tick, &input) in (message.start..).zip(message.inputs.iter()) {
+            if tick < self.confirmed {
+                continue;
+            }
+            if tick > self.confirmed {
+                break;
+            }
+            let index = tick as usize;
+            if index < self.inputs[remote].len() {
+                if tick < self.tick && self.inputs[remote][index] != input {
+                    self.rollback_from =
+                        Some(self.rollback_from.map_or(tick, |from| from.min(tick)));
+                }
+                self.inputs[remote][index] = input;
+            } else {
+                self.inputs[remote].push(input);
+            }
+            self.confirmed += 1;
+        }
+    }
+
+    pub fn advance(&mut self, world: &mut World, input: InputFrame) {
+        self.correct(world);
+        self.inputs[self.local].push(input);
+        self.simulate(world, self.tick);
+        self.tick += 1;
+    }
+
+    // Goes back to the first tick that was simulated with a wrong guess and replays from there.
+    pub fn correct(&mut self, world: &mut World) {
+        let Some(from) = self.rollback_from.take() else {
+            return;
+        };
+        let Some(snapshot) = self.snapshots.iter().find(|snapshot| snapshot.tick == from) else {
+            error!("No snapshot left for tick {from}, the peers may have desynced");
+            return;
+        };
+        snapshot.clone().restore(world);
+        self.rollbacks += 1;
+        for tick in from..self.tick {
+            self.simulate(world, tick);
+        }
+    }
+
+    fn simulate(&mut self, world: &mut World, tick: u32) {
+        self.predict(tick);
+        self.snapshots.retain(|snapshot| snapshot.tick < tick);
+        self.snapshots.push_back(Snapshot::save(world, tick));
+        while self.snapshots.len() > SNAPSHOT_HISTORY {
+            self.snapshots.pop_front();
+        }
+
+        let index = tick as usize;
+        let action_state = |inputs: &Vec<InputFrame>| {
+            let previous = index.checked_sub(1).map(|previous| inputs[previous]);
+            inputs[index].action_state(previous.unwrap_or_default())
+        };
+        world.insert_resource(RollbackInputs {
+            players: [action_state(&self.inputs[0]), action_state(&self.inputs[1])],
+        });
+        world.run_schedule(RollbackSchedule);
+    }
+
+    // The guess for a missing input is that the peer still holds what it held last.
+    fn predict(&mut self, tick: u32) {
+        if tick < self.confirmed {
+            return;
+        }
+        let remote = self.remote();
+        let guess = self
+            .confirmed
+            .checked_sub(1)
+            .map(|last| self.inputs[remote][last as usize])
+            .unwrap_or_default();
+        let index = tick as usize;
+        if index < self.inputs[remote].len() {
+            self.inputs[remote][index] = guess;
+        } else {
+            self.inputs[remote].push(guess);
+        }
+    }
+}
+
+// Equal on both peers once they have simulated the same inputs.
+pub fn checksum(world: &mut World) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (_, transform, velocity, acceleration, collider) in world
+        .query_filtered::<SavedState, With<Rollback>>()
+        .iter(world)
+    {
+        let values = [
+            transform.translation.to_array(),
+            transform.scale.to_array(),
+            velocity.value.to_array(),
+            acceleration.value.to_array(),
+        ];
+        for value in values.iter().flatten().chain(transform.rotation.as_ref()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        collider.radius.to_bits().hash(&mut hasher);
+    }
+    world.resource::<GameRng>().word_pos().hash(&mut hasher);
+    world.resource::<Lives>().remaining.hash(&mut hasher);
+    world.resource::<Score>().players.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn rollback_schedule() -> Schedule {
+    let mut schedule = Schedule::new(RollbackSchedule);
+    schedule.add_systems((steer_ships, fire_missiles, move_entities, detect_hits).chain());
+    schedule
+}
+
+// The UDP socket to the other player.
+#[derive(Resource, Debug)]
+pub struct PeerLink {
+    socket: UdpSocket,
+    peer: SocketAddr,
+}
+
+impl PeerLink {
+    pub fn bind(port: u16, peer: SocketAddr) -> Result<Self, NetError> {
+        Ok(Self {
+            socket: open_socket(SocketAddr::from(([0, 0, 0, 0], port)))?,
+            peer,
+        })
+    }
+}
+
+// `RollbackSession` and `PeerLink` are inserted along with the plugin, like `NetPlugin`.
+pub struct RollbackPlugin;
+
+impl Plugin for RollbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StartState(GameState::Versus))
+            .init_resource::<RollbackInputs>()
+            .add_schedule(rollback_schedule())
+            .add_systems(OnEnter(GameState::Versus), start_match)
+            .add_systems(
+                Update,
+                (advance_session, show_missiles)
+                    .chain()
+                    .run_if(in_state(GameState::Versus)),
+            );
+    }
+}
+
+pub fn start_match(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut lives: ResMut<Lives>,
+    mut score: ResMut<Score>,
+) {
+    lives.remaining = vec![VERSUS_LIVES; 2];
+    score.players = vec![0; 2];
+    for id in 0..2 {
+        let side = if id == 0 { -1.0 } else { 1.0 };
+        commands.spawn((
+            SceneBundle {
+                scene: game_assets.scene(SPACESHIP_SCENE),
+                transform: Transform::from_xyz(side * START_X, 0.0, 0.0)
+                    .with_rotation(Quat::from_rotation_y(-side * FRAC_PI_2)),
+                ..default()
+            },
+            Velocity::new(Vec3::ZERO),
+            Acceleration::new(Vec3::ZERO),
+            Collider::new(SPACESHIP_RADIUS),
+            Spaceship,
+            Player { id },
+            Rollback,
+        ));
+        for slot in 0..MISSILES_PER_SHIP {
+            commands.spawn((
+                SceneBundle {
+                    scene: game_assets.scene(MISSILE_SCENE),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                Velocity::new(Vec3::ZERO),
+                Acceleration::new(Vec3::ZERO),
+                // Spent missiles have no size.
+                Collider::new(0.0),
+                VersusMissile { owner: id, slot },
+                Rollback,
+            ));
+        }
+    }
+}
+
+// Ticks run at `TICK_SECONDS` whatever the frame rate, `lag` is the time not simulated yet.
+fn advance_session(world: &mut World, mut lag: Local<f32>) {
+    let delta = world.resource::<Time>().delta_seconds();
+    *lag = (*lag + delta).min(MAX_CATCH_UP_TICKS as f32 * TICK_SECONDS);
+    world.resource_scope(|world, mut session: Mut<RollbackSession>| {
+        let link = world.resource::<PeerLink>();
+        let mut messages = vec![];
+        while let Some((address, message)) = receive::<PeerMessage>(&link.socket) {
+            if address == link.peer {
+                messages.push(message);
+            }
+        }
+        for message in messages.iter() {
+            session.receive(message);
+        }
+
+        while *lag >= TICK_SECONDS && session.can_advance() {
+            *lag -= TICK_SECONDS;
+            let input = InputFrame::from_actions(world.resource::<PlayerActions>().get(0));
+            session.advance(world, input);
+        }
+        session.correct(world);
+        let link = world.resource::<PeerLink>();
+        send(&link.socket, link.peer, &session.message());
+    });
+}
+
+fn show_missiles(mut query: Query<(&Collider, &mut Visibility), With<VersusMissile>>) {
+    for (collider, mut visibility) in query.iter_mut() {
+        let shown = if collider.radius > 0.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+}
+
+type RollbackShip = (With<Spaceship>, With<Rollback>);
+
+fn steer_ships(
+    inputs: Res<RollbackInputs>,
+    mut query: Query<(&Player, &mut Transform, &mut Velocity), RollbackShip>,
+) {
+    for (player, mut transform, mut velocity) in query.iter_mut() {
+        velocity.value = steer_spaceship(&mut transform, &inputs.players[player.id], TICK_SECONDS);
+    }
+}
+
+type MissileState<'a> = (
+    &'a VersusMissile,
+    &'a mut Transform,
+    &'a mut Velocity,
+    &'a mut Collider,
+);
+
+fn fire_missiles(
+    inputs: Res<RollbackInputs>,
+    ship_query: Query<(&Player, &Transform), RollbackShip>,
+    mut missile_query: Query<MissileState, Without<Spaceship>>,
+) {
+    for (player, ship) in ship_query.iter() {
+        if !inputs.players[player.id].just_pressed(Action::Fire) {
+            continue;
+        }
+        let free = missile_query
+            .iter_mut()
+            .filter(|(missile, _, _, collider)| {
+                missile.owner == player.id && collider.radius == 0.0
+            })
+            .min_by_key(|(missile, _, _, _)| missile.slot);
+        let Some((_, mut transform, mut velocity, mut collider)) = free else {
+            continue;
+        };
+        let direction = ship.rotation * Vec3::Z;
+        *transform = Transform::from_translation(
+            ship.translation + direction * MISSILE_FORWARD_SPAWN_SCALAR,
+        )
+        .with_rotation(ship.rotation);
+        velocity.value = direction * MISSILE_SPEED;
+        collider.radius = MISSILE_RADIUS;
+    }
+}
+
+type MovingState<'a> = (
+    &'a mut Transform,
+    &'a mut Velocity,
+    &'a Acceleration,
+    &'a mut Collider,
+    Has<VersusMissile>,
+);
+
+fn move_entities(mut query: Query<MovingState, With<Rollback>>) {
+    for (mut transform, mut velocity, acceleration, mut collider, missile) in query.iter_mut() {
+        velocity.value += acceleration.value * TICK_SECONDS;
+        transform.translation += velocity.value * TICK_SECONDS;
+        if !missile {
+            transform.translation = transform.translation.clamp(-ARENA, ARENA);
+        } else if transform.translation.abs().cmpgt(ARENA).any() {
+            *transform = Transform::default();
+            velocity.value = Vec3::ZERO;
+            collider.radius = 0.0;
+        }
+    }
+}
+
+// A hit costs a life and sends the ship somewhere away from the other one, losing the last
+// life gives the other player the round. Ticks are simulated again after a rollback, so hits
+// never become new contacts and raise no `CollisionEvent`.
+fn detect_hits(
+    mut ship_query: Query<(Entity, &Player, &mut Transform, &mut Collider), RollbackShip>,
+    mut missile_query: Query<(Entity, MissileState), Without<Spaceship>>,
+    mut rng: ResMut<GameRng>,
+    mut lives: ResMut<Lives>,
+    mut score: ResMut<Score>,
+) {
+    let mut ships: Vec<(Entity, usize)> = vec![];
+    for (entity, player, _, mut collider) in ship_query.iter_mut() {
+        collider.colliding_entities.clear();
+        ships.push((entity, player.id));
+    }
+    ships.sort_by_key(|&(_, id)| id);
+
+    let mut missiles: Vec<_> = missile_query.iter_mut().collect();
+    missiles.sort_by_key(|(_, (missile, _, _, _))| (missile.owner, missile.slot));
+    for (missile_entity, (missile, transform, velocity, collider)) in missiles.iter_mut() {
+        collider.colliding_entities.clear();
+        if collider.radius == 0.0 {
+            continue;
+        }
+        for &(ship_entity, id) in ships.iter() {
+            if id == missile.owner {
+                continue;
+            }
+            let Ok((_, _, ship, mut ship_collider)) = ship_query.get_mut(ship_entity) else {
+                continue;
+            };
+            let reach = ship_collider.radius + collider.radius;
+            if ship.translation.distance(transform.translation) >= reach {
+                continue;
+            }
+            ship_collider.colliding_entities.push(*missile_entity);
+            collider.colliding_entities.push(ship_entity);
+            collider.radius = 0.0;
+            **transform = Transform::default();
+            velocity.value = Vec3::ZERO;
+
+            lives.remaining[id] = lives.remaining[id].saturating_sub(1);
+            if lives.remaining[id] == 0 {
+                score.add(missile.owner, 1);
+                lives.remaining = vec![VERSUS_LIVES; 2];
+            }
+            let others: Vec<(Vec3, f32)> = ship_query
+                .iter()
+                .filter(|(_, player, _, _)| player.id != id)
+                .map(|(_, _, other, _)| (other.translation, RESPAWN_CLEARANCE))
+                .collect();
+            if let Some(point) = find_safe_point(&mut **rng, &others) {
+                ship_query.get_mut(ship_entity).unwrap().2.translation = point;
+            }
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    const TICKS: u32 = 600;
+
+    fn peer_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<GameAssets>();
+        world.init_resource::<Lives>();
+        world.init_resource::<Score>();
+        world.insert_resource(GameRng::new(11));
+        world.add_schedule(rollback_schedule());
+        world.run_system_once(start_match);
+        world
+    }
+
+    // Drops some messages and delivers the rest a few frames late, in any order.
+    struct LossyLink {
+        rng: ChaCha8Rng,
+        in_flight: Vec<(u32, PeerMessage)>,
+    }
+
+    impl LossyLink {
+        fn new(seed: u64) -> Self {
+            Self {
+                rng: ChaCha8Rng::seed_from_u64(seed),
+                in_flight: vec![],
+            }
+        }
+
+        fn send(&mut self, frame: u32, message: PeerMessage) {
+            if self.rng.gen_bool(0.25) {
+                return;
+            }
+            let arrival = frame + self.rng.gen_range(1..6);
+            self.in_flight.push((arrival, message));
+        }
+
+        fn deliver(&mut self, frame: u32) -> Vec<PeerMessage> {
+            let (due, later) = self
+                .in_flight
+                .drain(..)
+                .partition(|&(arrival, _)| arrival <= frame);
+            self.in_flight = later;
+            due.into_iter().map(|(_, message)| message).collect()
+        }
+    }
+
+    #[test]
+    fn input_frames_keep_pressed_actions() {
+        let mut action_state = ActionState::default();
+        action_state.update(&[(Action::Fire, 1.0), (Action::TurnLeft, 0.3)]);
+        let input = InputFrame::from_actions(&action_state);
+        assert!(input.pressed(Action::Fire));
+        assert!(!input.pressed(Action::TurnLeft));
+        assert!(input
+            .action_state(InputFrame::default())
+            .just_pressed(Action::Fire));
+        assert!(!input.action_state(input).just_pressed(Action::Fire));
+    }
+
+    #[test]
+    fn peers_converge_over_a_lossy_link() {
+        let mut worlds = [peer_world(), peer_world()];
+        let mut sessions = [RollbackSession::new(0), RollbackSession::new(1)];
+        let mut links = [LossyLink::new(1), LossyLink::new(2)];
+        let mut scripts = [ChaCha8Rng::seed_from_u64(3), ChaCha8Rng::seed_from_u64(4)];
+        let mut inputs = [InputFrame::default(); 2];
+        let playable = [
+            Action::Thrust,
+            Action::TurnLeft,
+            Action::TurnRight,
+            Action::Fire,
+        ];
+
+        let mut frame = 0;
+        let converged = |sessions: &[RollbackSession; 2]| {
+            sessions.iter().all(|session| {
+                session.tick == TICKS
+                    && session.confirmed == TICKS
+                    && session.rollback_from.is_none()
+            })
+        };
+        while !converged(&sessions) {
+            assert!(frame < 10 * TICKS, "peers stopped making progress");
+            for peer in 0..2 {
+                for message in links[1 - peer].deliver(frame) {
+                    sessions[peer].receive(&message);
+                }
+                let session = &mut sessions[peer];
+                if session.tick < TICKS && session.can_advance() {
+                    if session.tick.checked_rem(15) == Some(0) {
+                        let mut action_state = ActionState::default();
+                        let held: Vec<(Action, f32)> = playable
+                            .iter()
+                            .filter(|_| scripts[peer].gen_bool(0.5))
+                            .map(|&action| (action, 1.0))
+                            .collect();
+                        action_state.update(&held);
+                        inputs[peer] = InputFrame::from_actions(&action_state);
+                    }
+                    session.advance(&mut worlds[peer], inputs[peer]);
+                } else {
+                    session.correct(&mut worlds[peer]);
+                }
+                links[peer].send(frame, session.message());
+            }
+            frame += 1;
+        }
+
+        assert!(sessions.iter().any(|session| session.rollbacks() > 0));
+        let [first, second] = &mut worlds;
+        assert_eq!(checksum(first), checksum(second));
+    }
+}
@@ -0,0 +1,46 @@
+use std::{fmt, fs, io, path::Path};
+
+use serde::de::DeserializeOwned;
+
+// Reading, parsing or writing RON, for the files the game keeps and the messages it sends.
+#[derive(Debug)]
+pub enum RonError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Write(ron::Error),
+}
+
+impl fmt::Display for RonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RonError::Io(err) => write!(f, "file error: {err}"),
+            RonError::Parse(err) => write!(f, "could not parse: {err}"),
+            RonError::Write(err) => write!(f, "could not serialize: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RonError {}
+
+impl From<io::Error> for RonError {
+    fn from(err: io::Error) -> Self {
+        RonError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for RonError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        RonError::Parse(err)
+    }
+}
+
+impl From<ron::Error> for RonError {
+    fn from(err: ron::Error) -> Self {
+        RonError::Write(err)
+    }
+}
+
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, RonError> {
+    let text = fs::read_to_string(path)?;
+    Ok(ron::from_str(&text)?)
+}
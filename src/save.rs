@@ -0,0 +1,607 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    reflect::DynamicStruct,
+    scene::{serde::SceneDeserializer, SceneSpawnError},
+    utils::HashMap,
+};
+use serde::{de::DeserializeSeed, Deserialize, Serialize};
+
+use crate::{
+    asset_loader::GameAssets,
+    asteroids::{Asteroid, ProceduralAsteroids, ASTEROID_SCENE},
+    collision_detection::{Collider, CollisionDamage},
+    console::ConsoleAppExt,
+    health::Health,
+    movement::{Acceleration, Velocity},
+    powerup::PowerUpEffects,
+    rng::GameRng,
+    ron_file::RonError,
+    score::Score,
+    shield::Shield,
+    spaceship::{Lives, Player, Spaceship, SpaceshipMissile, MISSILE_SCENE, SPACESHIP_SCENE},
+    state::GameState,
+    wave::Wave,
+};
+
+pub const SAVE_VERSION: u32 = 4;
+const DEFAULT_SAVE_PATH: &str = "savegame.ron";
+const QUICKSAVE_KEY: KeyCode = KeyCode::F5;
+const QUICKLOAD_KEY: KeyCode = KeyCode::F9;
+
+type SavedEntity = Or<(With<Spaceship>, With<Asteroid>, With<SpaceshipMissile>)>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    pub version: u32,
+    // One score and one count of lives left per player.
+    pub scores: Vec<u32>,
+    pub lives: Vec<u32>,
+    pub wave: u32,
+    pub rng_seed: u64,
+    pub rng_word_pos: u64,
+    // Ship, asteroids and missiles as a reflected `DynamicScene` in RON.
+    pub entities: String,
+}
+
+// Before co-op there was a single score.
+#[derive(Deserialize)]
+struct SaveGameV1 {
+    score: u32,
+    wave: u32,
+    rng_seed: u64,
+    rng_word_pos: u64,
+    entities: String,
+}
+
+impl From<SaveGameV1> for SaveGameV2 {
+    fn from(save: SaveGameV1) -> Self {
+        SaveGameV2 {
+            scores: vec![save.score],
+            wave: save.wave,
+            rng_seed: save.rng_seed,
+            rng_word_pos: save.rng_word_pos,
+            entities: save.entities,
+        }
+    }
+}
+
+// Asteroids could be saved without their model, from before there were variants. They are
+// given one by `add_asteroid_models` when the scene is parsed.
+#[derive(Deserialize)]
+struct SaveGameV2 {
+    scores: Vec<u32>,
+    wave: u32,
+    rng_seed: u64,
+    rng_word_pos: u64,
+    entities: String,
+}
+
+impl From<SaveGameV2> for SaveGameV3 {
+    fn from(save: SaveGameV2) -> Self {
+        SaveGameV3 {
+            scores: save.scores,
+            wave: save.wave,
+            rng_seed: save.rng_seed,
+            rng_word_pos: save.rng_word_pos,
+            entities: save.entities,
+        }
+    }
+}
+
+// Lives weren't saved, loading such a save leaves them as they are.
+#[derive(Deserialize)]
+struct SaveGameV3 {
+    scores: Vec<u32>,
+    wave: u32,
+    rng_seed: u64,
+    rng_word_pos: u64,
+    entities: String,
+}
+
+impl From<SaveGameV3> for SaveGame {
+    fn from(save: SaveGameV3) -> Self {
+        SaveGame {
+            version: SAVE_VERSION,
+            scores: save.scores,
+            lives: vec![],
+            wave: save.wave,
+            rng_seed: save.rng_seed,
+            rng_word_pos: save.rng_word_pos,
+            entities: save.entities,
+        }
+    }
+}
+
+// Spells out the model of asteroids that don't name one, they were all the first manifest
+// asteroid.
+fn add_asteroid_models(scene: &mut DynamicScene) {
+    let components = scene
+        .entities
+        .iter_mut()
+        .flat_map(|entity| entity.components.iter_mut());
+    for component in components {
+        let is_asteroid = component
+            .get_represented_type_info()
+            .is_some_and(|info| info.type_path() == Asteroid::type_path());
+        let Some(asteroid) = component.as_any_mut().downcast_mut::<DynamicStruct>() else {
+            continue;
+        };
+        if !is_asteroid {
+            continue;
+        }
+        if asteroid.field("variant").is_none() {
+            asteroid.insert("variant", 0_usize);
+        }
+        if asteroid.field("procedural").is_none() {
+            asteroid.insert("procedural", false);
+        }
+    }
+}
+
+// Read first so the rest of the file can be parsed as the right version.
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+impl SaveGame {
+    pub fn from_ron(text: &str) -> Result<Self, SaveError> {
+        let SaveVersion { version } = ron::from_str(text)?;
+        migrate(version, text)
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        Ok(ron::ser::to_string_pretty(self, default())?)
+    }
+}
+
+// Whenever `SaveGame` changes shape, bump `SAVE_VERSION`, keep the previous layout as a
+// `SaveGameV<n>` struct with a conversion to the next version, and add an arm here.
+fn migrate(version: u32, text: &str) -> Result<SaveGame, SaveError> {
+    match version {
+        1 => Ok(SaveGameV3::from(SaveGameV2::from(ron::from_str::<SaveGameV1>(text)?)).into()),
+        2 => Ok(SaveGameV3::from(ron::from_str::<SaveGameV2>(text)?).into()),
+        3 => Ok(ron::from_str::<SaveGameV3>(text)?.into()),
+        SAVE_VERSION => Ok(ron::from_str(text)?),
+        _ => Err(SaveError::UnsupportedVersion(version)),
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    File(RonError),
+    Spawn(SceneSpawnError),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::File(err) => write!(f, "save file error: {err}"),
+            SaveError::Spawn(err) => write!(f, "could not restore entities: {err}"),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save version {version} is not supported (current is {SAVE_VERSION})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl<E: Into<RonError>> From<E> for SaveError {
+    fn from(err: E) -> Self {
+        SaveError::File(err.into())
+    }
+}
+
+impl From<SceneSpawnError> for SaveError {
+    fn from(err: SceneSpawnError) -> Self {
+        SaveError::Spawn(err)
+    }
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            quicksave_controls.run_if(in_state(GameState::InGame)),
+        )
+        .add_console_command("save", "save [file]: save the game", save_command)
+        .add_console_command("load", "load [file]: load a saved game", load_command);
+    }
+}
+
+pub fn capture(world: &mut World) -> Result<SaveGame, SaveError> {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, SavedEntity>()
+        .iter(world)
+        .collect();
+    let scene = DynamicSceneBuilder::from_world(world)
+        .deny_all()
+        .allow::<Transform>()
+        .allow::<Velocity>()
+        .allow::<Acceleration>()
+        .allow::<Collider>()
+        .allow::<CollisionDamage>()
+        .allow::<Health>()
+        .allow::<Shield>()
+        .allow::<PowerUpEffects>()
+        .allow::<Spaceship>()
+        .allow::<Player>()
+        .allow::<SpaceshipMissile>()
+        .allow::<Asteroid>()
+        .extract_entities(entities.into_iter())
+        .build();
+    let rng = world.resource::<GameRng>();
+
+    Ok(SaveGame {
+        version: SAVE_VERSION,
+        scores: world.resource::<Score>().players.clone(),
+        lives: world.resource::<Lives>().remaining.clone(),
+        wave: world.resource::<Wave>().number,
+        rng_seed: rng.seed,
+        rng_word_pos: rng.word_pos(),
+        entities: scene.serialize_ron(world.resource::<AppTypeRegistry>())?,
+    })
+}
+
+pub fn restore(world: &mut World, save: &SaveGame) -> Result<(), SaveError> {
+    let mut scene = {
+        let registry = world.resource::<AppTypeRegistry>().read();
+        let mut deserializer = ron::de::Deserializer::from_str(&save.entities)?;
+        SceneDeserializer {
+            type_registry: &registry,
+        }
+        .deserialize(&mut deserializer)
+        .map_err(|err| deserializer.span_error(err))?
+    };
+    add_asteroid_models(&mut scene);
+
+    // Only what `capture` saves is replaced, UFOs, the boss and pickups carry on as they are.
+    let current: Vec<Entity> = world
+        .query_filtered::<Entity, SavedEntity>()
+        .iter(world)
+        .collect();
+    for entity in current {
+        world.entity_mut(entity).despawn_recursive();
+    }
+
+    let mut entity_map = HashMap::default();
+    scene.write_to_world(world, &mut entity_map)?;
+    attach_models(world, entity_map.values().copied());
+
+    world.resource_mut::<Score>().players = save.scores.clone();
+    if !save.lives.is_empty() {
+        world.resource_mut::<Lives>().remaining = save.lives.clone();
+    }
+    world.resource_mut::<Wave>().set(save.wave);
+    *world.resource_mut::<GameRng>() = GameRng::from_state(save.rng_seed, save.rng_word_pos);
+    Ok(())
+}
+
+// Models are not saved, they are looked up again from the entity's marker component.
+fn attach_models(world: &mut World, entities: impl Iterator<Item = Entity>) {
+    let procedural: Vec<Handle<Scene>> = world
+        .get_resource::<ProceduralAsteroids>()
+        .map(|procedural| {
+            procedural
+                .variants
+                .iter()
+                .map(|model| model.scene.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    world.resource_scope(|world, game_assets: Mut<GameAssets>| {
+        for entity in entities {
+            let mut entity_mut = world.entity_mut(entity);
+            let scene = if entity_mut.contains::<Spaceship>() {
+                // Saves from before power-ups were kept have ships without effects.
+                if !entity_mut.contains::<PowerUpEffects>() {
+                    entity_mut.insert(PowerUpEffects::default());
+                }
+                // Saves from before co-op have a lone ship without a player.
+                if !entity_mut.contains::<Player>() {
+                    entity_mut.insert(Player::default());
+                }
+                game_assets.scene(SPACESHIP_SCENE)
+            } else if let Some(asteroid) = entity_mut.get::<Asteroid>() {
+                match procedural.get(asteroid.variant) {
+                    Some(scene) if asteroid.procedural => scene.clone(),
+                    _ => game_assets.scene_variant(ASTEROID_SCENE, asteroid.variant),
+                }
+            } else if entity_mut.contains::<SpaceshipMissile>() {
+                game_assets.scene(MISSILE_SCENE)
+            } else {
+                continue;
+            };
+            let transform = entity_mut.get::<Transform>().copied().unwrap_or_default();
+            entity_mut.insert(SceneBundle {
+                scene,
+                transform,
+                global_transform: GlobalTransform::from(transform),
+                ..default()
+            });
+        }
+    });
+}
+
+pub fn save_to_file(world: &mut World, path: &Path) -> Result<(), SaveError> {
+    let text = capture(world)?.to_ron()?;
+    fs::write(path, text)?;
+    Ok(())
+}
+
+pub fn load_from_file(world: &mut World, path: &Path) -> Result<(), SaveError> {
+    let save = SaveGame::from_ron(&fs::read_to_string(path)?)?;
+    restore(world, &save)
+}
+
+fn quicksave_controls(world: &mut World) {
+    let keyboard_input = world.resource::<Input<KeyCode>>();
+    let (save, load) = (
+        keyboard_input.just_pressed(QUICKSAVE_KEY),
+        keyboard_input.just_pressed(QUICKLOAD_KEY),
+    );
+    let path = Path::new(DEFAULT_SAVE_PATH);
+    if save {
+        match save_to_file(world, path) {
+            Ok(()) => info!("Saved game to {path:?}"),
+            Err(err) => error!("Failed to save game: {err}"),
+        }
+    } else if load {
+        match load_from_file(world, path) {
+            Ok(()) => info!("Loaded game from {path:?}"),
+            Err(err) => error!("Failed to load game: {err}"),
+        }
+    }
+}
+
+fn path_argument(args: &[&str]) -> Result<PathBuf, String> {
+    match args {
+        [] => Ok(PathBuf::from(DEFAULT_SAVE_PATH)),
+        [path] => Ok(PathBuf::from(path)),
+        _ => Err("expected at most one file name".into()),
+    }
+}
+
+fn save_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let path = path_argument(args)?;
+    save_to_file(world, &path).map_err(|err| err.to_string())?;
+    Ok(format!("saved to {path:?}"))
+}
+
+fn load_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let path = path_argument(args)?;
+    load_from_file(world, &path).map_err(|err| err.to_string())?;
+    Ok(format!("loaded {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::ufo::Ufo;
+
+    fn test_world() -> World {
+        let mut app = App::new();
+        app.register_type::<Transform>()
+            .register_type::<Vec3>()
+            .register_type::<Quat>()
+            .register_type::<Velocity>()
+            .register_type::<Acceleration>()
+            .register_type::<Collider>()
+            .register_type::<CollisionDamage>()
+            .register_type::<Health>()
+            .register_type::<Shield>()
+            .register_type::<PowerUpEffects>()
+            .register_type::<Spaceship>()
+            .register_type::<Player>()
+            .register_type::<SpaceshipMissile>()
+            .register_type::<Asteroid>()
+            .init_resource::<GameAssets>()
+            .init_resource::<Score>()
+            .init_resource::<Lives>()
+            .insert_resource(Wave::new(3))
+            .insert_resource(GameRng::new(7));
+        std::mem::take(&mut app.world)
+    }
+
+    #[test]
+    fn restores_captured_world() {
+        let mut world = test_world();
+        let mut shield = Shield::default();
+        shield.energy = 42.0;
+        world.spawn((
+            Transform::from_xyz(1.0, 0.0, 2.0),
+            Velocity::new(Vec3::X),
+            Collider::new(5.0),
+            Health::new(40.0),
+            shield,
+            PowerUpEffects {
+                rapid_fire: 3.0,
+                ..default()
+            },
+            Spaceship,
+            Player { id: 1 },
+        ));
+        world.spawn((
+            Transform::from_xyz(-3.0, 0.0, 4.0),
+            Health::new(12.0),
+            Asteroid {
+                size: 1.5,
+                variant: 0,
+                procedural: false,
+            },
+        ));
+        world.resource_mut::<Score>().players = vec![300, 200];
+        world.resource_mut::<Lives>().remaining = vec![2, 1];
+        let save = capture(&mut world).unwrap();
+        let save = SaveGame::from_ron(&save.to_ron().unwrap()).unwrap();
+
+        world.clear_entities();
+        world.resource_mut::<Score>().players.clear();
+        world.resource_mut::<Lives>().remaining.clear();
+        world.resource_mut::<Wave>().set(1);
+        restore(&mut world, &save).unwrap();
+
+        let (transform, velocity, collider, health, player) = world
+            .query_filtered::<(&Transform, &Velocity, &Collider, &Health, &Player), With<Spaceship>>()
+            .single(&world);
+        assert_eq!(transform.translation, Vec3::new(1.0, 0.0, 2.0));
+        assert_eq!(velocity.value, Vec3::X);
+        assert_eq!(collider.radius, 5.0);
+        assert_eq!(health.value, 40.0);
+        assert_eq!(player.id, 1);
+        let (global_transform, shield, effects) = world
+            .query_filtered::<(&GlobalTransform, &Shield, &PowerUpEffects), With<Spaceship>>()
+            .single(&world);
+        assert_eq!(global_transform.translation(), Vec3::new(1.0, 0.0, 2.0));
+        assert_eq!(shield.energy, 42.0);
+        assert_eq!(effects.rapid_fire, 3.0);
+        let asteroid = world.query::<&Asteroid>().single(&world);
+        assert_eq!(asteroid.size, 1.5);
+        assert_eq!(world.resource::<Score>().players, vec![300, 200]);
+        assert_eq!(world.resource::<Lives>().remaining, vec![2, 1]);
+        assert_eq!(world.resource::<Wave>().number, 3);
+    }
+
+    #[test]
+    fn restore_keeps_unsaved_entities() {
+        let mut world = test_world();
+        world.spawn((Transform::default(), Health::new(1.0), Spaceship));
+        let ufo = world
+            .spawn((Transform::default(), Health::new(30.0), Ufo::default()))
+            .id();
+        let save = capture(&mut world).unwrap();
+        let save = SaveGame::from_ron(&save.to_ron().unwrap()).unwrap();
+
+        restore(&mut world, &save).unwrap();
+        assert_eq!(
+            world.get::<Health>(ufo).map(|health| health.value),
+            Some(30.0)
+        );
+        assert_eq!(world.query::<&Ufo>().iter(&world).count(), 1);
+        assert_eq!(world.query::<&Spaceship>().iter(&world).count(), 1);
+    }
+
+    #[test]
+    fn restores_rng_position() {
+        let mut world = test_world();
+        let mut expected = GameRng::new(7);
+        world.resource_mut::<GameRng>().gen::<u64>();
+        expected.gen::<u64>();
+        let save = capture(&mut world).unwrap();
+
+        *world.resource_mut::<GameRng>() = GameRng::new(1);
+        restore(&mut world, &save).unwrap();
+        assert_eq!(
+            world.resource_mut::<GameRng>().gen::<u64>(),
+            expected.gen::<u64>()
+        );
+    }
+
+    #[test]
+    fn hand_written_scene_uses_defaults() {
+        let mut world = test_world();
+        let text = r#"(
+            resources: {},
+            entities: {
+                0: (
+                    components: {
+                        "bevy_game::asteroids::Asteroid": (),
+                        "bevy_game::collision_detection::Collider": (),
+                        "bevy_game::movement::Velocity": (value: (x: 1.0, y: 0.0, z: 0.0)),
+                        "bevy_game::movement::Acceleration": (),
+                    },
+                ),
+            },
+        )"#;
+        let scene = {
+            let registry = world.resource::<AppTypeRegistry>().read();
+            let mut deserializer = ron::de::Deserializer::from_str(text).unwrap();
+            SceneDeserializer {
+                type_registry: &registry,
+            }
+            .deserialize(&mut deserializer)
+            .unwrap()
+        };
+        scene
+            .write_to_world(&mut world, &mut HashMap::default())
+            .unwrap();
+
+        let (asteroid, collider, velocity, acceleration) = world
+            .query::<(&Asteroid, &Collider, &Velocity, &Acceleration)>()
+            .single(&world);
+        assert_eq!(asteroid.size, 1.0);
+        assert_eq!(collider.radius, 1.0);
+        assert_eq!(velocity.value, Vec3::X);
+        assert_eq!(acceleration.value, Vec3::ZERO);
+    }
+
+    #[test]
+    fn migrates_single_score_saves() {
+        let text =
+            r#"(version: 1, score: 700, wave: 2, rng_seed: 5, rng_word_pos: 0, entities: "")"#;
+        let save = SaveGame::from_ron(text).unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.scores, vec![700]);
+        assert_eq!(save.wave, 2);
+    }
+
+    #[test]
+    fn migrates_asteroids_without_a_model() {
+        let mut world = test_world();
+        let entities = r#"(
+            resources: {},
+            entities: {
+                0: (
+                    components: {
+                        "bevy_game::asteroids::Asteroid": (
+                            size: 2.0,
+                        ),
+                        "bevy_game::health::Health": (value: 3.0),
+                    },
+                ),
+                1: (
+                    components: {
+                        "bevy_game::asteroids::Asteroid": (size: 1.0, variant: 2, procedural: true),
+                    },
+                ),
+            },
+        )"#;
+        let text = format!(
+            "(version: 2, scores: [10], wave: 1, rng_seed: 5, rng_word_pos: 0, entities: {})",
+            ron::to_string(entities).unwrap()
+        );
+        let save = SaveGame::from_ron(&text).unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert!(save.lives.is_empty());
+
+        restore(&mut world, &save).unwrap();
+        let mut asteroids: Vec<(f32, usize, bool)> = world
+            .query::<&Asteroid>()
+            .iter(&world)
+            .map(|asteroid| (asteroid.size, asteroid.variant, asteroid.procedural))
+            .collect();
+        asteroids.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(asteroids, vec![(1.0, 2, true), (2.0, 0, false)]);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let text = "(version: 99, score: 0)";
+        assert!(matches!(
+            SaveGame::from_ron(text),
+            Err(SaveError::UnsupportedVersion(99))
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::Asteroid,
+    boss::Boss,
+    collision_detection::apply_collision_damage,
+    health::DamageEvent,
+    powerup::{PowerUpEffects, SCORE_MULTIPLIER},
+    spaceship::{Lives, Player, Spaceship, SpaceshipMissile},
+    state::GameState,
+    ufo::Ufo,
+};
+
+const ASTEROID_POINTS: u32 = 100;
+const UFO_POINTS: u32 = 500;
+const BOSS_POINTS: u32 = 5000;
+const FONT_SIZE: f32 = 24.0;
+
+#[derive(Resource, Reflect, Debug, Default, Clone)]
+#[reflect(Resource)]
+pub struct Score {
+    // Points per player, grown as players score.
+    pub players: Vec<u32>,
+}
+
+impl Score {
+    pub fn total(&self) -> u32 {
+        self.players.iter().sum()
+    }
+
+    pub fn add(&mut self, player: usize, points: u32) {
+        if self.players.len() <= player {
+            self.players.resize(player + 1, 0);
+        }
+        self.players[player] += points;
+    }
+}
+
+#[derive(Component, Debug)]
+struct ScoreText;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .register_type::<Score>()
+            .add_systems(Startup, spawn_score_text)
+            .add_systems(
+                Update,
+                (
+                    score_destroyed_targets.after(apply_collision_damage),
+                    update_score_text,
+                ),
+            )
+            .add_systems(OnEnter(GameState::GameOver), reset_score);
+    }
+}
+
+fn spawn_score_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(12.0),
+            ..default()
+        }),
+        ScoreText,
+    ));
+}
+
+// Kills are credited to whoever fired the missile, doubled while their multiplier runs.
+fn score_destroyed_targets(
+    mut score: ResMut<Score>,
+    mut damage_event_reader: EventReader<DamageEvent>,
+    asteroid_query: Query<(), With<Asteroid>>,
+    ufo_query: Query<(), With<Ufo>>,
+    boss_query: Query<(), With<Boss>>,
+    missile_query: Query<&SpaceshipMissile>,
+    spaceship_query: Query<(&Player, &PowerUpEffects), With<Spaceship>>,
+) {
+    for event in damage_event_reader.read() {
+        // Only the hit that takes the target below zero counts.
+        let killed = event.health_left <= 0.0 && event.health_left + event.amount > 0.0;
+        let Ok(missile) = missile_query.get(event.source) else {
+            continue;
+        };
+        if !killed {
+            continue;
+        }
+        let points = if asteroid_query.contains(event.entity) {
+            ASTEROID_POINTS
+        } else if ufo_query.contains(event.entity) {
+            UFO_POINTS
+        } else if boss_query.contains(event.entity) {
+            BOSS_POINTS
+        } else {
+            continue;
+        };
+        let multiplied = spaceship_query
+            .iter()
+            .any(|(player, effects)| player.id == missile.owner && effects.score_multiplier > 0.0);
+        let multiplier = if multiplied { SCORE_MULTIPLIER } else { 1 };
+        score.add(missile.owner, points * multiplier);
+    }
+}
+
+fn update_score_text(
+    score: Res<Score>,
+    lives: Res<Lives>,
+    mut query: Query<&mut Text, With<ScoreText>>,
+) {
+    if !score.is_changed() && !lives.is_changed() {
+        return;
+    }
+    let value = if lives.remaining.len() > 1 {
+        lives
+            .remaining
+            .iter()
+            .enumerate()
+            .map(|(id, remaining)| {
+                let points = score.players.get(id).copied().unwrap_or_default();
+                format!("P{}: {points}\nLives: {remaining}", id + 1)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        format!(
+            "Score: {}\nLives: {}",
+            score.total(),
+            lives.remaining.first().copied().unwrap_or_default()
+        )
+    };
+    for mut text in query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    info!("Game over, final score {}", score.total());
+    score.players.clear();
+}
@@ -0,0 +1,223 @@
+use bevy::{prelude::*, utils::HashSet};
+use rand::Rng;
+
+use crate::{
+    asset_loader::GameAssets,
+    asteroids::{asteroid_spawn, ProceduralAsteroids},
+    config::GameConfig,
+    despawn::PlayArea,
+    pool::{PoolAppExt, PoolCommandsExt, SpawnPooled},
+    rng::GameRng,
+    spaceship::Spaceship,
+    state::GameState,
+};
+
+pub const SECTOR_SIZE: f32 = 100.0;
+// Sectors this many steps from a ship's are loaded, and only unloaded once they are further
+// than `UNLOAD_RADIUS` so flying along a border doesn't keep reloading them.
+const LOAD_RADIUS: i32 = 1;
+const UNLOAD_RADIUS: i32 = 2;
+// Asteroids keep clear of where the ships start out.
+const START_CLEARANCE: f32 = 35.0;
+// Sectors range from empty to this many times `sector_asteroids`.
+const MAX_DENSITY: f32 = 3.0;
+
+// An asteroid streamed in with a sector, it goes again when the sector is unloaded.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component, Default)]
+pub struct SectorMember {
+    pub sector: IVec2,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct LoadedSectors {
+    pub sectors: HashSet<IVec2>,
+}
+
+pub struct SectorPlugin;
+
+impl Plugin for SectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SectorMember>()
+            .reset_on_retire::<SectorMember>()
+            .init_resource::<LoadedSectors>()
+            .add_systems(
+                Update,
+                (follow_ships, stream_sectors)
+                    .chain()
+                    .run_if(in_state(GameState::InGame).and_then(sector_world)),
+            )
+            .add_systems(OnEnter(GameState::GameOver), reset_sectors);
+    }
+}
+
+fn sector_world(config: Res<GameConfig>) -> bool {
+    config.sector_world
+}
+
+// Sector (0, 0) is centred on the origin.
+pub fn sector_of(position: Vec3) -> IVec2 {
+    (Vec2::new(position.x, position.z) / SECTOR_SIZE + 0.5)
+        .floor()
+        .as_ivec2()
+}
+
+// Everything in a sector is rolled from this, so it looks the same every time it is loaded.
+fn sector_rng(seed: u64, sector: IVec2) -> GameRng {
+    let coordinates = (sector.x as u32 as u64) << 32 | sector.y as u32 as u64;
+    GameRng::new(mix(seed ^ mix(coordinates)))
+}
+
+// SplitMix64's finaliser, so neighbouring sectors get unrelated seeds.
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+// Where a sector's asteroids start out. Squaring the roll makes sparse sectors common and dense
+// fields rare, while averaging out at `average`.
+fn asteroid_positions(rng: &mut GameRng, sector: IVec2, average: f32) -> Vec<Vec3> {
+    let density = rng.gen::<f32>().powi(2) * MAX_DENSITY;
+    let count = (average.max(0.0) * density).round() as usize;
+    let center = Vec3::new(sector.x as f32, 0.0, sector.y as f32) * SECTOR_SIZE;
+    let half = SECTOR_SIZE / 2.0;
+    (0..count)
+        .map(|_| center + Vec3::new(rng.gen_range(-half..half), 0.0, rng.gen_range(-half..half)))
+        .filter(|position| position.length() > START_CLEARANCE)
+        .collect()
+}
+
+// Which sectors to load and unload with ships in the `occupied` sectors.
+fn plan_streaming(loaded: &HashSet<IVec2>, occupied: &[IVec2]) -> (Vec<IVec2>, Vec<IVec2>) {
+    let steps = |sector: IVec2| {
+        occupied
+            .iter()
+            .map(|&ship| (sector - ship).abs().max_element())
+            .min()
+            .unwrap_or(i32::MAX)
+    };
+    let mut load = vec![];
+    for &ship in occupied {
+        for x in -LOAD_RADIUS..=LOAD_RADIUS {
+            for z in -LOAD_RADIUS..=LOAD_RADIUS {
+                let sector = ship + IVec2::new(x, z);
+                if !loaded.contains(&sector) && !load.contains(&sector) {
+                    load.push(sector);
+                }
+            }
+        }
+    }
+    let unload = loaded
+        .iter()
+        .copied()
+        .filter(|&sector| steps(sector) > UNLOAD_RADIUS)
+        .collect();
+    (load, unload)
+}
+
+// Despawning, UFOs, bosses and hyperspace all work around the ships instead of the origin.
+fn follow_ships(
+    mut play_area: ResMut<PlayArea>,
+    spaceship_query: Query<&Transform, With<Spaceship>>,
+) {
+    let ships: Vec<Vec3> = spaceship_query
+        .iter()
+        .map(|transform| transform.translation)
+        .collect();
+    if !ships.is_empty() {
+        play_area.center = ships.iter().sum::<Vec3>() / ships.len() as f32;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn stream_sectors(
+    mut commands: Commands,
+    mut loaded: ResMut<LoadedSectors>,
+    game_assets: Res<GameAssets>,
+    procedural: Res<ProceduralAsteroids>,
+    config: Res<GameConfig>,
+    rng: Res<GameRng>,
+    spaceship_query: Query<&Transform, With<Spaceship>>,
+    member_query: Query<(Entity, &SectorMember)>,
+) {
+    let occupied: Vec<IVec2> = spaceship_query
+        .iter()
+        .map(|transform| sector_of(transform.translation))
+        .collect();
+    if occupied.is_empty() {
+        return;
+    }
+    let (load, unload) = plan_streaming(&loaded.sectors, &occupied);
+
+    for sector in unload {
+        loaded.sectors.remove(&sector);
+        for (entity, member) in member_query.iter() {
+            if member.sector == sector {
+                commands.retire(entity);
+            }
+        }
+        debug!("Unloaded sector {sector}");
+    }
+
+    let procedural = config.procedural_asteroids.then_some(&*procedural);
+    for sector in load {
+        let mut sector_rng = sector_rng(rng.seed, sector);
+        let positions = asteroid_positions(&mut sector_rng, sector, config.sector_asteroids);
+        for &position in positions.iter() {
+            let spawn = asteroid_spawn(&game_assets, procedural, position, &mut sector_rng, false);
+            commands.spawn_pooled(SpawnPooled {
+                scene: spawn.scene,
+                transform: spawn.transform,
+                bundle: (spawn.bundle, SectorMember { sector }),
+            });
+        }
+        loaded.sectors.insert(sector);
+        debug!("Loaded sector {sector} with {} asteroids", positions.len());
+    }
+}
+
+// The next run streams its sectors in from scratch, the game over clears out their asteroids.
+fn reset_sectors(mut loaded: ResMut<LoadedSectors>, mut play_area: ResMut<PlayArea>) {
+    loaded.sectors.clear();
+    play_area.center = Vec3::ZERO;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sectors_reproduce_their_contents() {
+        let sector = IVec2::new(3, -2);
+        let first = asteroid_positions(&mut sector_rng(42, sector), sector, 12.0);
+        let again = asteroid_positions(&mut sector_rng(42, sector), sector, 12.0);
+        assert_eq!(first, again);
+        assert!(first.iter().all(|&position| sector_of(position) == sector));
+
+        let counts: Vec<usize> = (0..20)
+            .map(|x| {
+                let sector = IVec2::new(x, 5);
+                asteroid_positions(&mut sector_rng(42, sector), sector, 12.0).len()
+            })
+            .collect();
+        assert!(counts.iter().min() < counts.iter().max());
+    }
+
+    #[test]
+    fn streaming_loads_around_ships_and_unloads_far_sectors() {
+        let (load, unload) = plan_streaming(&HashSet::default(), &[IVec2::ZERO]);
+        assert_eq!(load.len(), 9);
+        assert!(unload.is_empty());
+
+        let loaded: HashSet<IVec2> = load.into_iter().collect();
+        let (load, unload) = plan_streaming(&loaded, &[IVec2::new(1, 0)]);
+        assert_eq!(load.len(), 3);
+        // Two steps away is still kept.
+        assert!(unload.is_empty());
+
+        let (_, unload) = plan_streaming(&loaded, &[IVec2::new(3, 0)]);
+        assert_eq!(unload.len(), 6);
+        assert!(unload.iter().all(|sector| sector.x <= 0));
+    }
+}
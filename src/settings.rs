@@ -0,0 +1,106 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::{default_bindings, Action, Binding},
+    ron_file::{self, RonError},
+};
+
+pub const DEFAULT_SETTINGS_PATH: &str = "settings.ron";
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ControlScheme {
+    // Turn and thrust relative to where the ship points.
+    #[default]
+    Classic,
+    // Move in screen directions and point the ship at the mouse cursor.
+    MouseAim,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    // Looks down on the whole arena.
+    #[default]
+    Fixed,
+    // Trails the ship, leading where it is heading and backing off as it speeds up.
+    Follow,
+}
+
+// Player preferences, as opposed to the game tunables in `GameConfig`. Changed from the
+// console and written back to the file they were loaded from.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub control_scheme: ControlScheme,
+    // One list per player.
+    pub bindings: Vec<Vec<(Action, Binding)>>,
+    // Stick travel below this is ignored.
+    pub stick_dead_zone: f32,
+    pub camera_mode: CameraMode,
+    // Scales how hard hits and explosions shake the camera, 0 turns it off.
+    pub screen_shake: f32,
+    // How far the radar reaches around the ship, 0 hides it.
+    pub radar_range: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            control_scheme: ControlScheme::default(),
+            bindings: vec![default_bindings(0), default_bindings(1)],
+            stick_dead_zone: 0.2,
+            camera_mode: CameraMode::default(),
+            screen_shake: 1.0,
+            radar_range: 150.0,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Result<Self, RonError> {
+        ron_file::read(path)
+    }
+
+    // A missing file just means nothing has been changed yet.
+    pub fn load_or_default(path: &Path) -> Result<Self, RonError> {
+        match Self::load(path) {
+            Err(RonError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            result => result,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RonError> {
+        let text = ron::ser::to_string_pretty(self, default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+// Where `Settings` are saved to when they change.
+#[derive(Resource, Debug, Clone)]
+pub struct SettingsFile(pub PathBuf);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_through_ron() {
+        let settings = Settings::default();
+        let text = ron::ser::to_string_pretty(&settings, default()).unwrap();
+        let loaded: Settings = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.bindings, settings.bindings);
+    }
+
+    #[test]
+    fn missing_keys_keep_their_defaults() {
+        let settings: Settings = ron::from_str("(stick_dead_zone: 0.5)").unwrap();
+        assert_eq!(settings.stick_dead_zone, 0.5);
+        assert_eq!(settings.bindings[1], default_bindings(1));
+    }
+}
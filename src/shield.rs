@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+
+use crate::{
+    action::{Action, PlayerActions},
+    asteroids::Asteroid,
+    collision_detection::{collision_detection, handle_collisions, Collider},
+    movement::Velocity,
+    spaceship::{Player, Spaceship, SPACESHIP_RADIUS},
+    state::GameState,
+};
+
+const MAX_ENERGY: f32 = 100.0;
+const DRAIN_PER_SECOND: f32 = 30.0;
+const REGEN_PER_SECOND: f32 = 15.0;
+// Energy only starts coming back after the shield has been down for a moment.
+const REGEN_DELAY_SECONDS: f32 = 1.0;
+// Raising the shield with less than this left would only flicker it.
+const MIN_RAISE_ENERGY: f32 = 10.0;
+const DEFLECT_ENERGY: f32 = 8.0;
+const SHIELD_RADIUS: f32 = 8.0;
+const BUBBLE_COLOR: Color = Color::rgba(0.3, 0.7, 1.0, 0.25);
+const FONT_SIZE: f32 = 20.0;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct Shield {
+    pub energy: f32,
+    pub active: bool,
+    regen_delay: f32,
+}
+
+impl Default for Shield {
+    fn default() -> Self {
+        Self {
+            energy: MAX_ENERGY,
+            active: false,
+            regen_delay: 0.0,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct ShieldBubble;
+
+#[derive(Component, Debug)]
+struct ShieldText;
+
+#[derive(Resource, Debug, Default)]
+struct ShieldAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Shield>()
+            .init_resource::<ShieldAssets>()
+            .add_systems(Startup, (build_shield_assets, spawn_shield_text))
+            .add_systems(
+                Update,
+                (
+                    attach_shield,
+                    shield_controls,
+                    deflect_asteroids
+                        .after(collision_detection)
+                        .before(handle_collisions::<Asteroid>)
+                        .before(handle_collisions::<Spaceship>),
+                    update_bubble,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Update, update_shield_text);
+    }
+}
+
+fn build_shield_assets(
+    mut shield_assets: ResMut<ShieldAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    shield_assets.mesh = meshes.add(Mesh::from(shape::UVSphere {
+        radius: SHIELD_RADIUS,
+        ..default()
+    }));
+    shield_assets.material = materials.add(StandardMaterial {
+        base_color: BUBBLE_COLOR,
+        emissive: BUBBLE_COLOR * 0.5,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+}
+
+// Every new ship, including respawned and loaded ones, gets its bubble. Loaded ships keep the
+// shield they were saved with, the others start with a full one.
+fn attach_shield(
+    mut commands: Commands,
+    query: Query<(Entity, Has<Shield>), Added<Spaceship>>,
+    shield_assets: Res<ShieldAssets>,
+) {
+    for (entity, has_shield) in query.iter() {
+        let mut entity_commands = commands.entity(entity);
+        if !has_shield {
+            entity_commands.insert(Shield::default());
+        }
+        entity_commands.with_children(|parent| {
+            parent.spawn((
+                PbrBundle {
+                    mesh: shield_assets.mesh.clone(),
+                    material: shield_assets.material.clone(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                ShieldBubble,
+            ));
+        });
+    }
+}
+
+fn shield_controls(
+    mut query: Query<(&Player, &mut Shield, &mut Collider), With<Spaceship>>,
+    player_actions: Res<PlayerActions>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    for (player, mut shield, mut collider) in query.iter_mut() {
+        let held = player_actions.get(player.id).pressed(Action::Shield);
+        shield.active =
+            held && shield.energy > 0.0 && (shield.active || shield.energy >= MIN_RAISE_ENERGY);
+
+        if shield.active {
+            shield.energy = (shield.energy - DRAIN_PER_SECOND * delta).max(0.0);
+            shield.regen_delay = REGEN_DELAY_SECONDS;
+        } else if shield.regen_delay > 0.0 {
+            shield.regen_delay -= delta;
+        } else {
+            shield.energy = (shield.energy + REGEN_PER_SECOND * delta).min(MAX_ENERGY);
+        }
+        collider.radius = if shield.active {
+            SHIELD_RADIUS
+        } else {
+            SPACESHIP_RADIUS
+        };
+    }
+}
+
+// Asteroids touching a raised shield bounce off it. The contact is taken out of both colliders
+// so neither side takes collision damage.
+type ShieldedShip<'a> = (
+    Entity,
+    &'a mut Shield,
+    &'a mut Collider,
+    &'a Transform,
+    &'a Velocity,
+);
+
+fn deflect_asteroids(
+    mut spaceship_query: Query<ShieldedShip, (With<Spaceship>, Without<Asteroid>)>,
+    mut asteroid_query: Query<(&mut Collider, &mut Transform, &mut Velocity), With<Asteroid>>,
+) {
+    for (ship_entity, mut shield, mut ship_collider, ship_transform, ship_velocity) in
+        spaceship_query.iter_mut()
+    {
+        if !shield.active {
+            continue;
+        }
+        let ship = ship_transform.translation;
+        let mut deflected = vec![];
+        for &entity in ship_collider.colliding_entities.iter() {
+            let Ok((mut collider, mut transform, mut velocity)) = asteroid_query.get_mut(entity)
+            else {
+                continue;
+            };
+            let offset = transform.translation - ship;
+            let normal = Vec3::new(offset.x, 0.0, offset.z)
+                .try_normalize()
+                .unwrap_or(Vec3::X);
+            let approach = (velocity.value - ship_velocity.value).dot(normal);
+            if approach < 0.0 {
+                velocity.value -= 2.0 * approach * normal;
+                shield.energy = (shield.energy - DEFLECT_ENERGY).max(0.0);
+            }
+            transform.translation = ship + normal * (SHIELD_RADIUS + collider.radius);
+            collider
+                .colliding_entities
+                .retain(|&other| other != ship_entity);
+            collider.new_contacts.retain(|&other| other != ship_entity);
+            deflected.push(entity);
+        }
+        ship_collider
+            .colliding_entities
+            .retain(|entity| !deflected.contains(entity));
+        ship_collider
+            .new_contacts
+            .retain(|entity| !deflected.contains(entity));
+    }
+}
+
+fn update_bubble(
+    spaceship_query: Query<(&Shield, &Children), With<Spaceship>>,
+    mut bubble_query: Query<&mut Visibility, With<ShieldBubble>>,
+) {
+    for (shield, children) in spaceship_query.iter() {
+        let mut iter = bubble_query.iter_many_mut(children);
+        while let Some(mut visibility) = iter.fetch_next() {
+            *visibility = if shield.active {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
+
+fn spawn_shield_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: FONT_SIZE,
+                color: BUBBLE_COLOR.with_a(1.0),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            right: Val::Px(12.0),
+            ..default()
+        }),
+        ShieldText,
+    ));
+}
+
+fn update_shield_text(
+    spaceship_query: Query<(&Player, &Shield), With<Spaceship>>,
+    mut text_query: Query<&mut Text, With<ShieldText>>,
+) {
+    let mut shields: Vec<(usize, f32)> = spaceship_query
+        .iter()
+        .map(|(player, shield)| (player.id, shield.energy / MAX_ENERGY * 100.0))
+        .collect();
+    shields.sort_by_key(|&(id, _)| id);
+    let value = match shields.as_slice() {
+        [] => "Shield 0%".to_string(),
+        [(_, percent)] => format!("Shield {percent:.0}%"),
+        _ => shields
+            .iter()
+            .map(|(id, percent)| format!("P{} Shield {percent:.0}%", id + 1))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::collision_detection::CollisionEvent;
+
+    #[test]
+    fn asteroids_bounce_off_a_raised_shield() {
+        let mut world = World::new();
+        world.init_resource::<Events<CollisionEvent>>();
+        let asteroid = world
+            .spawn((
+                Asteroid::default(),
+                Collider::new(2.0),
+                Transform::from_xyz(9.0, 0.0, 0.0),
+                GlobalTransform::from_xyz(9.0, 0.0, 0.0),
+                Velocity::new(Vec3::new(-10.0, 0.0, 3.0)),
+            ))
+            .id();
+        let ship = world
+            .spawn((
+                Spaceship,
+                Shield {
+                    active: true,
+                    ..default()
+                },
+                Collider::new(SHIELD_RADIUS),
+                Transform::default(),
+                GlobalTransform::default(),
+                Velocity::new(Vec3::ZERO),
+            ))
+            .id();
+
+        world.run_system_once(collision_detection);
+        world.run_system_once(deflect_asteroids);
+        world.run_system_once(handle_collisions::<Spaceship>);
+
+        assert!(world.resource::<Events<CollisionEvent>>().is_empty());
+        let velocity = world.get::<Velocity>(asteroid).unwrap().value;
+        assert_eq!(velocity, Vec3::new(10.0, 0.0, 3.0));
+        assert_eq!(
+            world.get::<Transform>(asteroid).unwrap().translation,
+            Vec3::new(SHIELD_RADIUS + 2.0, 0.0, 0.0)
+        );
+        assert!(world
+            .get::<Collider>(ship)
+            .unwrap()
+            .colliding_entities
+            .is_empty());
+        assert!(world
+            .get::<Collider>(asteroid)
+            .unwrap()
+            .colliding_entities
+            .is_empty());
+        assert_eq!(
+            world.get::<Shield>(ship).unwrap().energy,
+            MAX_ENERGY - DEFLECT_ENERGY
+        );
+    }
+}
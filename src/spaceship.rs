@@ -1,114 +1,350 @@
-use bevy::{
-    ecs::query,
-    input::keyboard::{self, KeyboardInput},
-    prelude::*,
-    transform,
-};
+use bevy::prelude::*;
 
 use crate::{
-    asset_loader::SceneAssets,
-    collision_detection::Collider,
-    movement::{self, Acceleration, MovingObejectBundle, Velocity},
+    action::{Action, ActionState, PlayerActions},
+    asset_loader::GameAssets,
+    camera::{CursorPosition, GameCamera},
+    collision_detection::{Collider, CollisionDamage},
+    config::GameConfig,
+    console::ConsoleAppExt,
+    health::{Health, Invulnerable},
+    movement::{Acceleration, MovingObejectBundle, Velocity},
+    pool::{PoolCommandsExt, SpawnPooled},
+    powerup::{PowerUpEffects, ShieldInvulnerable},
+    settings::{ControlScheme, Settings},
+    shield::ShieldBubble,
+    state::GameState,
 };
 
+pub const SPACESHIP_SCENE: &str = "spaceship";
+pub const MISSILE_SCENE: &str = "missile";
 const STARTING_TRANSLATION: Vec3 = Vec3::new(0.0, 0.0, -20.0);
+// Co-op ships start side by side this far apart.
+const PLAYER_SPACING: f32 = 15.0;
+// Player one keeps the model's own colours.
+const PLAYER_TINTS: [Color; 4] = [
+    Color::WHITE,
+    Color::rgb(1.0, 0.55, 0.3),
+    Color::rgb(0.5, 1.0, 0.5),
+    Color::rgb(0.9, 0.5, 1.0),
+];
 const SPACESHIP_SPEED: f32 = 25.0;
 const SPACESHIP_ROATATION_SPEED: f32 = 2.5;
 const SPACESHIP_ROLL_SPEED: f32 = 2.5;
 const MISSILE_SPEED: f32 = 50.0;
 const MISSILE_FORWARD_SPAWN_SCALAR: f32 = 7.5;
-const SPACESHIP_RADIUS: f32 = 5.0;
+pub const SPACESHIP_RADIUS: f32 = 5.0;
+const SPACESHIP_HEALTH: f32 = 100.0;
+const SPACESHIP_COLLISION_DAMAGE: f32 = 100.0;
 const MISSILE_RADIUS: f32 = 1.0;
+const MISSILE_HEALTH: f32 = 1.0;
+const MISSILE_COLLISION_DAMAGE: f32 = 5.0;
+const MULTI_SHOT_SPREAD: f32 = 0.15;
+// A fresh ship gets a moment to get out of the way of whatever killed the last one.
+const RESPAWN_SHIELD_SECONDS: f32 = 2.0;
 
-#[derive(Component, Debug)]
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, Default)]
 pub struct Spaceship;
 
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct Player {
+    pub id: usize,
+}
+
+impl Player {
+    pub fn tint(self) -> Color {
+        PLAYER_TINTS[self.id % PLAYER_TINTS.len()]
+    }
+}
+
+// Missiles remember who fired them so kills are credited to the right player.
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct SpaceshipMissile {
+    pub owner: usize,
+}
+
+// Set once a ship's model has had its materials tinted in the player's colour.
 #[derive(Component, Debug)]
-pub struct SpaceshipMissile;
+struct Tinted;
+
+// Lives left for each player.
+#[derive(Resource, Reflect, Debug, Default, Clone)]
+#[reflect(Resource)]
+pub struct Lives {
+    pub remaining: Vec<u32>,
+}
 
 pub struct SpaceshipPlugin;
 
 impl Plugin for SpaceshipPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostStartup, spawn_spaceship).add_systems(
-            Update,
-            (spaceship_movement_controls, spaceship_weapon_controls),
-        );
+        app.register_type::<Spaceship>()
+            .register_type::<Player>()
+            .register_type::<SpaceshipMissile>()
+            .register_type::<Lives>()
+            .init_resource::<Lives>()
+            .add_systems(OnEnter(GameState::InGame), (reset_lives, spawn_spaceship))
+            .add_systems(
+                Update,
+                (
+                    spaceship_movement_controls,
+                    spaceship_weapon_controls,
+                    spaceship_destroyed,
+                )
+                    .run_if(in_state(GameState::InGame)),
+            )
+            // Also tints ships shown by network clients.
+            .add_systems(Update, tint_spaceships)
+            .add_console_command("god", "toggle spaceship invulnerability", god_command);
     }
 }
 
-fn spawn_spaceship(mut commands: Commands, scene_assets: Res<SceneAssets>) {
-    commands.spawn((
+fn spaceship_bundle(game_assets: &GameAssets, player: Player, players: u32) -> impl Bundle {
+    let offset = player.id as f32 - (players.max(1) - 1) as f32 / 2.0;
+    (
         MovingObejectBundle {
             velocity: Velocity { value: Vec3::ZERO },
             acceleration: Acceleration { value: Vec3::ZERO },
             collider: Collider::new(SPACESHIP_RADIUS),
             model: SceneBundle {
-                scene: scene_assets.spaceship.clone(),
-                transform: Transform::from_translation(STARTING_TRANSLATION),
+                scene: game_assets.scene(SPACESHIP_SCENE),
+                transform: Transform::from_translation(
+                    STARTING_TRANSLATION + Vec3::X * offset * PLAYER_SPACING,
+                ),
                 ..default()
             },
         },
         Spaceship,
-    ));
+        player,
+        Health::new(SPACESHIP_HEALTH),
+        CollisionDamage::new(SPACESHIP_COLLISION_DAMAGE),
+        PowerUpEffects::default(),
+    )
+}
+
+fn reset_lives(mut lives: ResMut<Lives>, config: Res<GameConfig>) {
+    lives.remaining = vec![config.starting_lives.max(1); config.players.max(1) as usize];
+}
+
+fn spawn_spaceship(mut commands: Commands, game_assets: Res<GameAssets>, config: Res<GameConfig>) {
+    for id in 0..config.players.max(1) as usize {
+        commands.spawn(spaceship_bundle(
+            &game_assets,
+            Player { id },
+            config.players,
+        ));
+    }
 }
 
 fn spaceship_movement_controls(
-    mut query: Query<(&mut Transform, &mut Velocity), With<Spaceship>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    mut query: Query<(&Player, &mut Transform, &mut Velocity), With<Spaceship>>,
+    camera_query: Query<&GlobalTransform, With<GameCamera>>,
+    player_actions: Res<PlayerActions>,
+    settings: Res<Settings>,
+    cursor_position: Res<CursorPosition>,
     time: Res<Time>,
 ) {
-    let (mut transform, mut velocity) = query.single_mut();
-    let mut rotation = 0.0;
-    let mut roll = 0.0;
-    let mut movement = 0.0;
-
-    if keyboard_input.pressed(KeyCode::D) {
-        rotation = -SPACESHIP_ROATATION_SPEED * time.delta_seconds();
-    } else if keyboard_input.pressed(KeyCode::A) {
-        rotation = SPACESHIP_ROATATION_SPEED * time.delta_seconds();
-    }
+    for (player, mut transform, mut velocity) in query.iter_mut() {
+        let action_state = player_actions.get(player.id);
+        // There's only one mouse, it belongs to the first player.
+        if settings.control_scheme == ControlScheme::MouseAim && player.id == 0 {
+            // Thrust and turn keys move the ship up, down and sideways on screen.
+            let Ok(camera) = camera_query.get_single() else {
+                continue;
+            };
+            let up = Vec3::new(camera.up().x, 0.0, camera.up().z).normalize_or_zero();
+            let right = Vec3::new(camera.right().x, 0.0, camera.right().z).normalize_or_zero();
+            let movement = up * action_state.axis(Action::Reverse, Action::Thrust)
+                + right * action_state.axis(Action::TurnLeft, Action::TurnRight);
+            velocity.value = movement.clamp_length_max(1.0) * SPACESHIP_SPEED;
 
-    if keyboard_input.pressed(KeyCode::S) {
-        movement = -SPACESHIP_SPEED;
-    } else if keyboard_input.pressed(KeyCode::W) {
-        movement = SPACESHIP_SPEED;
-    }
+            let Some(target) = cursor_position.world else {
+                continue;
+            };
+            let aim = target - transform.translation;
+            if aim.x != 0.0 || aim.z != 0.0 {
+                transform.rotation = Quat::from_rotation_y(aim.x.atan2(aim.z));
+            }
+            continue;
+        }
 
-    if keyboard_input.pressed(KeyCode::ShiftLeft) {
-        roll = -SPACESHIP_ROLL_SPEED * time.delta_seconds();
-    } else if keyboard_input.pressed(KeyCode::ControlLeft) {
-        roll = SPACESHIP_ROLL_SPEED * time.delta_seconds();
+        velocity.value = steer_spaceship(&mut transform, action_state, time.delta_seconds());
     }
+}
+
+// Classic turning, rolling and thrust relative to where the ship points, returns the new
+// velocity. Network clients also use this to predict their own ship.
+pub fn steer_spaceship(transform: &mut Transform, action_state: &ActionState, delta: f32) -> Vec3 {
+    let rotation =
+        action_state.axis(Action::TurnRight, Action::TurnLeft) * SPACESHIP_ROATATION_SPEED * delta;
+    let roll =
+        action_state.axis(Action::RollLeft, Action::RollRight) * SPACESHIP_ROLL_SPEED * delta;
+    let movement = action_state.axis(Action::Reverse, Action::Thrust) * SPACESHIP_SPEED;
 
     transform.rotate_y(rotation);
     transform.rotate_local_z(roll);
 
-    velocity.value = -transform.forward() * movement;
+    -transform.forward() * movement
 }
 
+// A missile every frame the trigger is held. Rapid fire adds a second one, half a frame's flight
+// further out.
 fn spaceship_weapon_controls(
     mut commands: Commands,
-    query: Query<&Transform, With<Spaceship>>,
-    scene_assets: Res<SceneAssets>,
-    keyboard_input: Res<Input<KeyCode>>,
+    query: Query<(&Player, &Transform, &PowerUpEffects), With<Spaceship>>,
+    game_assets: Res<GameAssets>,
+    player_actions: Res<PlayerActions>,
+    time: Res<Time>,
 ) {
-    let transform = query.single();
-    if keyboard_input.pressed(KeyCode::Space) {
-        commands.spawn((
-            MovingObejectBundle {
-                velocity: Velocity::new(-transform.forward() * MISSILE_SPEED),
-                acceleration: Acceleration::new(Vec3::ZERO),
-                collider: Collider::new(MISSILE_RADIUS),
-                model: SceneBundle {
-                    scene: scene_assets.missiles.clone(),
+    for (player, transform, effects) in query.iter() {
+        if !player_actions.get(player.id).pressed(Action::Fire) {
+            continue;
+        }
+
+        let leads: &[f32] = if effects.rapid_fire > 0.0 {
+            &[0.0, 0.5]
+        } else {
+            &[0.0]
+        };
+        let angles: &[f32] = if effects.multi_shot > 0.0 {
+            &[-MULTI_SHOT_SPREAD, 0.0, MULTI_SHOT_SPREAD]
+        } else {
+            &[0.0]
+        };
+        for &lead in leads {
+            let distance =
+                MISSILE_FORWARD_SPAWN_SCALAR + lead * MISSILE_SPEED * time.delta_seconds();
+            for &angle in angles {
+                let rotation = Quat::from_rotation_y(angle) * transform.rotation;
+                let direction = rotation * Vec3::Z;
+                commands.spawn_pooled(SpawnPooled {
+                    scene: game_assets.scene(MISSILE_SCENE),
                     transform: Transform::from_translation(
-                        transform.translation - transform.forward() * MISSILE_FORWARD_SPAWN_SCALAR,
-                    ).with_rotation(transform.rotation),
+                        transform.translation + direction * distance,
+                    )
+                    .with_rotation(rotation),
+                    bundle: (
+                        Velocity::new(direction * MISSILE_SPEED),
+                        Acceleration::new(Vec3::ZERO),
+                        Collider::new(MISSILE_RADIUS),
+                        SpaceshipMissile { owner: player.id },
+                        Health::new(MISSILE_HEALTH),
+                        CollisionDamage::new(MISSILE_COLLISION_DAMAGE),
+                    ),
+                });
+            }
+        }
+    }
+}
+
+// Losing a ship costs its player a life, the game is only over once every player has used up
+// all of theirs.
+fn spaceship_destroyed(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut lives: ResMut<Lives>,
+    game_assets: Res<GameAssets>,
+    config: Res<GameConfig>,
+    query: Query<&Player, With<Spaceship>>,
+) {
+    let flying: Vec<usize> = query.iter().map(|player| player.id).collect();
+    for id in 0..lives.remaining.len() {
+        if flying.contains(&id) || lives.remaining[id] == 0 {
+            continue;
+        }
+        lives.remaining[id] -= 1;
+        if lives.remaining[id] == 0 {
+            info!("Player {} is out of lives", id + 1);
+            continue;
+        }
+        info!(
+            "Spaceship destroyed, {} lives left for player {}",
+            lives.remaining[id],
+            id + 1
+        );
+        commands
+            .spawn(spaceship_bundle(
+                &game_assets,
+                Player { id },
+                config.players,
+            ))
+            .insert((
+                PowerUpEffects {
+                    shield: RESPAWN_SHIELD_SECONDS,
                     ..default()
                 },
-            },
-            SpaceshipMissile,
-        ));
+                Invulnerable,
+                ShieldInvulnerable,
+            ));
+    }
+    if flying.is_empty() && lives.remaining.iter().all(|&remaining| remaining == 0) {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+type UntintedShip = (With<Spaceship>, Without<Tinted>);
+
+// Gives every material in a ship's model its own copy in the player's colour. The model may
+// take a few frames to spawn, so ships are retried until it has.
+fn tint_spaceships(
+    mut commands: Commands,
+    spaceship_query: Query<(Entity, &Player), UntintedShip>,
+    children_query: Query<&Children>,
+    mut material_query: Query<&mut Handle<StandardMaterial>, Without<ShieldBubble>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, &player) in spaceship_query.iter() {
+        let tint = player.tint();
+        let mut found = false;
+        for descendant in children_query.iter_descendants(entity) {
+            let Ok(mut handle) = material_query.get_mut(descendant) else {
+                continue;
+            };
+            found = true;
+            if tint == Color::WHITE {
+                continue;
+            }
+            let Some(material) = materials.get(handle.id()) else {
+                continue;
+            };
+            let mut material = material.clone();
+            material.base_color *= tint.as_rgba_f32();
+            *handle = materials.add(material);
+        }
+        if found {
+            commands.entity(entity).insert(Tinted);
+        }
+    }
+}
+
+fn god_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    // A shield's invulnerability isn't god mode, turning god mode on takes it over so the shield
+    // running out leaves it on.
+    let mut query = world
+        .query_filtered::<(Entity, Has<Invulnerable>, Has<ShieldInvulnerable>), With<Spaceship>>();
+    let ships: Vec<(Entity, bool)> = query
+        .iter(world)
+        .map(|(entity, invulnerable, from_shield)| (entity, invulnerable && !from_shield))
+        .collect();
+    let Some(&(_, god_mode)) = ships.first() else {
+        return Err("there is no spaceship".into());
+    };
+    for (entity, _) in ships {
+        if god_mode {
+            world.entity_mut(entity).remove::<Invulnerable>();
+        } else {
+            world
+                .entity_mut(entity)
+                .insert(Invulnerable)
+                .remove::<ShieldInvulnerable>();
+        }
     }
+    Ok(if god_mode {
+        "god mode off".into()
+    } else {
+        "god mode on".into()
+    })
 }
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum GameState {
+    #[default]
+    Loading,
+    LoadingFailed,
+    InGame,
+    GameOver,
+    // Shows a network server's game, nothing is simulated locally.
+    Remote,
+    // A peer-to-peer match between two ships, simulated in rolled back ticks.
+    Versus,
+}
+
+// Where to go once the assets have loaded.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct StartState(pub GameState);
+
+impl Default for StartState {
+    fn default() -> Self {
+        Self(GameState::InGame)
+    }
+}
+
+pub struct StatePlugin;
+
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>()
+            .init_resource::<StartState>()
+            .add_systems(OnEnter(GameState::GameOver), restart_game);
+    }
+}
+
+// Everything is torn down on entering `GameOver`, so the run can start over straight away.
+fn restart_game(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
+}
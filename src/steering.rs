@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+// Wander steers towards a point on a circle projected in front of the mover.
+const WANDER_DISTANCE: f32 = 6.0;
+const WANDER_RADIUS: f32 = 3.0;
+
+// Each behaviour returns the acceleration that turns the current velocity into the desired
+// one. Movement stays on the XZ plane like everything else in the game.
+
+pub fn seek(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let desired = flatten(target - position).normalize_or_zero() * max_speed;
+    desired - velocity
+}
+
+pub fn flee(position: Vec3, velocity: Vec3, threat: Vec3, max_speed: f32) -> Vec3 {
+    let desired = flatten(position - threat).normalize_or_zero() * max_speed;
+    desired - velocity
+}
+
+// Flees from where the threat will be by the time it could reach us.
+pub fn evade(
+    position: Vec3,
+    velocity: Vec3,
+    threat: Vec3,
+    threat_velocity: Vec3,
+    max_speed: f32,
+) -> Vec3 {
+    let closing_speed = (threat_velocity - velocity).length().max(max_speed);
+    let lookahead = position.distance(threat) / closing_speed;
+    flee(
+        position,
+        velocity,
+        threat + threat_velocity * lookahead,
+        max_speed,
+    )
+}
+
+// `angle` should drift a little every frame to get a meandering path.
+pub fn wander(velocity: Vec3, angle: f32, max_speed: f32) -> Vec3 {
+    let heading = flatten(velocity).try_normalize().unwrap_or(Vec3::X);
+    let target =
+        heading * WANDER_DISTANCE + Vec3::new(angle.cos(), 0.0, angle.sin()) * WANDER_RADIUS;
+    target.normalize_or_zero() * max_speed - velocity
+}
+
+// Picks the closest of several targets, e.g. when more than one ship is in play.
+pub fn nearest<T>(
+    position: Vec3,
+    candidates: impl IntoIterator<Item = (Vec3, T)>,
+) -> Option<(Vec3, T)> {
+    candidates
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.distance(position).total_cmp(&b.distance(position)))
+}
+
+fn flatten(vector: Vec3) -> Vec3 {
+    Vec3::new(vector.x, 0.0, vector.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seek_and_flee_point_opposite_ways() {
+        let target = Vec3::new(10.0, 0.0, 0.0);
+        let seek = seek(Vec3::ZERO, Vec3::ZERO, target, 5.0);
+        let flee = flee(Vec3::ZERO, Vec3::ZERO, target, 5.0);
+        assert_eq!(seek, Vec3::new(5.0, 0.0, 0.0));
+        assert_eq!(flee, -seek);
+    }
+
+    #[test]
+    fn seek_cancels_current_velocity() {
+        let steering = seek(Vec3::ZERO, Vec3::new(0.0, 0.0, 5.0), Vec3::X, 5.0);
+        assert_eq!(steering, Vec3::new(5.0, 0.0, -5.0));
+    }
+
+    #[test]
+    fn evade_leads_the_threat() {
+        // A threat passing to the side moving towards +z pushes us towards -z as well as away.
+        let steering = evade(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::new(10.0, 0.0, -10.0),
+            Vec3::new(0.0, 0.0, 20.0),
+            5.0,
+        );
+        assert!(steering.x < 0.0);
+        assert!(steering.z < 0.0);
+        assert!(steering.z.abs() < steering.x.abs());
+    }
+
+    #[test]
+    fn wander_keeps_speed_on_the_plane() {
+        for step in 0..16 {
+            let steering = wander(Vec3::new(1.0, 0.0, 0.0), step as f32 * 0.4, 5.0);
+            let velocity = Vec3::new(1.0, 0.0, 0.0) + steering;
+            assert_eq!(velocity.y, 0.0);
+            assert!((velocity.length() - 5.0).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::{app::AppExit, prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asteroids::Asteroid,
+    collision_detection::{apply_collision_damage, CollisionEvent},
+    health::DamageEvent,
+    rng::GameRng,
+    spaceship::{Spaceship, SpaceshipMissile},
+    state::GameState,
+    wave::Wave,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Spaceship,
+    Asteroid,
+    Missile,
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    RunStarted {
+        seed: u64,
+        wave: u32,
+    },
+    Spawned {
+        entity: u64,
+        kind: EntityKind,
+        position: [f32; 3],
+    },
+    Despawned {
+        entity: u64,
+        kind: EntityKind,
+    },
+    ShotFired {
+        entity: u64,
+        position: [f32; 3],
+    },
+    Collision {
+        entity: u64,
+        kind: EntityKind,
+        other: u64,
+        other_kind: EntityKind,
+    },
+    Damage {
+        entity: u64,
+        kind: EntityKind,
+        source: u64,
+        source_kind: EntityKind,
+        amount: f32,
+        health_left: f32,
+    },
+    RunSummary {
+        duration: f32,
+        wave: u32,
+        shots: u32,
+        asteroids_destroyed: u32,
+    },
+}
+
+// One line of the telemetry file. `time` is game time in seconds since startup.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TelemetryRecord {
+    pub time: f32,
+    #[serde(flatten)]
+    pub event: TelemetryEvent,
+}
+
+impl TelemetryRecord {
+    pub fn read_all(reader: impl BufRead) -> io::Result<Vec<Self>> {
+        reader
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| {
+                serde_json::from_str(&line?)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            })
+            .collect()
+    }
+}
+
+pub struct TelemetryPlugin {
+    pub path: PathBuf,
+}
+
+#[derive(Resource)]
+struct TelemetryLog {
+    writer: BufWriter<File>,
+}
+
+impl TelemetryLog {
+    fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    fn write(&mut self, time: &Time, event: TelemetryEvent) {
+        let record = TelemetryRecord {
+            time: time.elapsed_seconds(),
+            event,
+        };
+        let result = serde_json::to_writer(&mut self.writer, &record)
+            .map_err(io::Error::from)
+            .and_then(|()| self.writer.write_all(b"\n"));
+        if let Err(err) = result {
+            error!("Failed to write telemetry: {err}");
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct RunStats {
+    started_at: f32,
+    shots: u32,
+    asteroids_destroyed: u32,
+}
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        let log = match TelemetryLog::create(&self.path) {
+            Ok(log) => log,
+            Err(err) => {
+                error!("Telemetry disabled, cannot create {:?}: {err}", self.path);
+                return;
+            }
+        };
+        app.insert_resource(log)
+            .init_resource::<RunStats>()
+            .add_systems(OnEnter(GameState::InGame), record_run_start)
+            .add_systems(OnEnter(GameState::GameOver), record_run_summary)
+            // Damage has to be logged before the dead entities are despawned at the end of `Update`.
+            .add_systems(
+                Update,
+                (record_collisions, record_damage).after(apply_collision_damage),
+            )
+            .add_systems(PostUpdate, (record_spawns, record_despawns))
+            .add_systems(Last, flush_on_exit);
+    }
+}
+
+fn kind_of(
+    entity: Entity,
+    spaceship_query: &Query<(), With<Spaceship>>,
+    asteroid_query: &Query<(), With<Asteroid>>,
+    missile_query: &Query<(), With<SpaceshipMissile>>,
+) -> EntityKind {
+    if spaceship_query.contains(entity) {
+        EntityKind::Spaceship
+    } else if asteroid_query.contains(entity) {
+        EntityKind::Asteroid
+    } else if missile_query.contains(entity) {
+        EntityKind::Missile
+    } else {
+        EntityKind::Other
+    }
+}
+
+fn record_run_start(
+    mut log: ResMut<TelemetryLog>,
+    mut stats: ResMut<RunStats>,
+    rng: Res<GameRng>,
+    wave: Res<Wave>,
+    time: Res<Time>,
+) {
+    *stats = RunStats {
+        started_at: time.elapsed_seconds(),
+        ..default()
+    };
+    log.write(
+        &time,
+        TelemetryEvent::RunStarted {
+            seed: rng.seed,
+            wave: wave.number,
+        },
+    );
+}
+
+fn record_run_summary(
+    mut log: ResMut<TelemetryLog>,
+    stats: Res<RunStats>,
+    wave: Res<Wave>,
+    time: Res<Time>,
+) {
+    log.write(
+        &time,
+        TelemetryEvent::RunSummary {
+            duration: time.elapsed_seconds() - stats.started_at,
+            wave: wave.number,
+            shots: stats.shots,
+            asteroids_destroyed: stats.asteroids_destroyed,
+        },
+    );
+}
+
+type JustSpawned = Or<(Added<Spaceship>, Added<Asteroid>, Added<SpaceshipMissile>)>;
+
+fn record_spawns(
+    mut log: ResMut<TelemetryLog>,
+    mut stats: ResMut<RunStats>,
+    query: Query<(Entity, &Transform), JustSpawned>,
+    spaceship_query: Query<(), With<Spaceship>>,
+    asteroid_query: Query<(), With<Asteroid>>,
+    missile_query: Query<(), With<SpaceshipMissile>>,
+    time: Res<Time>,
+) {
+    for (entity, transform) in query.iter() {
+        let position = transform.translation.to_array();
+        let event = match kind_of(entity, &spaceship_query, &asteroid_query, &missile_query) {
+            EntityKind::Missile => {
+                stats.shots += 1;
+                TelemetryEvent::ShotFired {
+                    entity: entity.to_bits(),
+                    position,
+                }
+            }
+            kind => TelemetryEvent::Spawned {
+                entity: entity.to_bits(),
+                kind,
+                position,
+            },
+        };
+        log.write(&time, event);
+    }
+}
+
+// Only the kinds the report counts are followed. UFOs, their projectiles and the boss come and
+// go unlogged, and show up as `Other` in collisions and damage.
+fn record_despawns(
+    mut log: ResMut<TelemetryLog>,
+    mut removed_spaceships: RemovedComponents<Spaceship>,
+    mut removed_asteroids: RemovedComponents<Asteroid>,
+    mut removed_missiles: RemovedComponents<SpaceshipMissile>,
+    time: Res<Time>,
+) {
+    let removed = removed_spaceships
+        .read()
+        .map(|entity| (entity, EntityKind::Spaceship))
+        .chain(
+            removed_asteroids
+                .read()
+                .map(|entity| (entity, EntityKind::Asteroid)),
+        )
+        .chain(
+            removed_missiles
+                .read()
+                .map(|entity| (entity, EntityKind::Missile)),
+        );
+    for (entity, kind) in removed {
+        log.write(
+            &time,
+            TelemetryEvent::Despawned {
+                entity: entity.to_bits(),
+                kind,
+            },
+        );
+    }
+}
+
+fn record_collisions(
+    mut log: ResMut<TelemetryLog>,
+    mut collision_event_reader: EventReader<CollisionEvent>,
+    spaceship_query: Query<(), With<Spaceship>>,
+    asteroid_query: Query<(), With<Asteroid>>,
+    missile_query: Query<(), With<SpaceshipMissile>>,
+    time: Res<Time>,
+) {
+    for event in collision_event_reader.read() {
+        let kind = |entity| kind_of(entity, &spaceship_query, &asteroid_query, &missile_query);
+        log.write(
+            &time,
+            TelemetryEvent::Collision {
+                entity: event.entity.to_bits(),
+                kind: kind(event.entity),
+                other: event.collided_entity.to_bits(),
+                other_kind: kind(event.collided_entity),
+            },
+        );
+    }
+}
+
+fn record_damage(
+    mut log: ResMut<TelemetryLog>,
+    mut stats: ResMut<RunStats>,
+    mut damage_event_reader: EventReader<DamageEvent>,
+    spaceship_query: Query<(), With<Spaceship>>,
+    asteroid_query: Query<(), With<Asteroid>>,
+    missile_query: Query<(), With<SpaceshipMissile>>,
+    time: Res<Time>,
+) {
+    for event in damage_event_reader.read() {
+        let kind = |entity| kind_of(entity, &spaceship_query, &asteroid_query, &missile_query);
+        let target_kind = kind(event.entity);
+        // Health can go below zero several times in the frame it dies, count it once.
+        if target_kind == EntityKind::Asteroid
+            && event.health_left <= 0.0
+            && event.health_left + event.amount > 0.0
+        {
+            stats.asteroids_destroyed += 1;
+        }
+        log.write(
+            &time,
+            TelemetryEvent::Damage {
+                entity: event.entity.to_bits(),
+                kind: target_kind,
+                source: event.source.to_bits(),
+                source_kind: kind(event.source),
+                amount: event.amount,
+                health_left: event.health_left,
+            },
+        );
+    }
+}
+
+fn flush_on_exit(
+    mut log: ResMut<TelemetryLog>,
+    mut exit_events: EventReader<AppExit>,
+    stats: Res<RunStats>,
+    wave: Res<Wave>,
+    time: Res<Time>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    log.write(
+        &time,
+        TelemetryEvent::RunSummary {
+            duration: time.elapsed_seconds() - stats.started_at,
+            wave: wave.number,
+            shots: stats.shots,
+            asteroids_destroyed: stats.asteroids_destroyed,
+        },
+    );
+    if let Err(err) = log.writer.flush() {
+        error!("Failed to flush telemetry: {err}");
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct TelemetryReport {
+    pub runs: u32,
+    pub shots: u32,
+    pub hits: u32,
+    pub asteroids_destroyed: u32,
+    pub times_to_kill: Vec<f32>,
+    pub survival_times: Vec<f32>,
+}
+
+impl TelemetryReport {
+    pub fn from_records(records: &[TelemetryRecord]) -> Self {
+        let mut report = Self::default();
+        let mut asteroid_spawn_times = HashMap::new();
+        let mut missiles_that_hit = HashSet::new();
+
+        for record in records {
+            match &record.event {
+                TelemetryEvent::ShotFired { .. } => report.shots += 1,
+                TelemetryEvent::Spawned {
+                    entity,
+                    kind: EntityKind::Asteroid,
+                    ..
+                } => {
+                    asteroid_spawn_times.insert(*entity, record.time);
+                }
+                TelemetryEvent::Damage {
+                    entity,
+                    kind,
+                    source,
+                    source_kind,
+                    amount,
+                    health_left,
+                } => {
+                    if *source_kind == EntityKind::Missile && *kind != EntityKind::Missile {
+                        missiles_that_hit.insert(*source);
+                    }
+                    let killed = *health_left <= 0.0 && health_left + amount > 0.0;
+                    if *kind == EntityKind::Asteroid && killed {
+                        report.asteroids_destroyed += 1;
+                        if let Some(spawned_at) = asteroid_spawn_times.remove(entity) {
+                            report.times_to_kill.push(record.time - spawned_at);
+                        }
+                    }
+                }
+                TelemetryEvent::RunSummary { duration, .. } => {
+                    report.runs += 1;
+                    report.survival_times.push(*duration);
+                }
+                _ => {}
+            }
+        }
+        report.hits = missiles_that_hit.len() as u32;
+        report
+    }
+
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.shots > 0).then(|| self.hits as f32 / self.shots as f32)
+    }
+}
+
+// Mean, min and max of a sample.
+pub fn summarize(values: &[f32]) -> Option<(f32, f32, f32)> {
+    if values.is_empty() {
+        return None;
+    }
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    Some((mean, min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(time: f32, event: TelemetryEvent) -> TelemetryRecord {
+        TelemetryRecord { time, event }
+    }
+
+    fn damage(entity: u64, kind: EntityKind, source: u64, health_left: f32) -> TelemetryEvent {
+        TelemetryEvent::Damage {
+            entity,
+            kind,
+            source,
+            source_kind: EntityKind::Missile,
+            amount: 5.0,
+            health_left,
+        }
+    }
+
+    #[test]
+    fn round_trips_json_lines() {
+        let records = vec![
+            record(0.0, TelemetryEvent::RunStarted { seed: 7, wave: 1 }),
+            record(
+                1.5,
+                TelemetryEvent::ShotFired {
+                    entity: 3,
+                    position: [1.0, 0.0, 2.0],
+                },
+            ),
+        ];
+        let text: String = records
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap() + "\n")
+            .collect();
+        assert!(text.starts_with(r#"{"time":0.0,"event":"run_started""#));
+        assert_eq!(TelemetryRecord::read_all(text.as_bytes()).unwrap(), records);
+    }
+
+    #[test]
+    fn report_accuracy_and_time_to_kill() {
+        let asteroid = EntityKind::Asteroid;
+        let records = vec![
+            record(
+                1.0,
+                TelemetryEvent::Spawned {
+                    entity: 1,
+                    kind: asteroid,
+                    position: [0.0; 3],
+                },
+            ),
+            record(
+                2.0,
+                TelemetryEvent::ShotFired {
+                    entity: 10,
+                    position: [0.0; 3],
+                },
+            ),
+            record(
+                2.1,
+                TelemetryEvent::ShotFired {
+                    entity: 11,
+                    position: [0.0; 3],
+                },
+            ),
+            record(
+                2.2,
+                TelemetryEvent::ShotFired {
+                    entity: 12,
+                    position: [0.0; 3],
+                },
+            ),
+            record(2.5, damage(1, asteroid, 10, 3.0)),
+            record(3.0, damage(1, asteroid, 11, -2.0)),
+            record(3.0, damage(1, asteroid, 11, -7.0)),
+            record(
+                9.0,
+                TelemetryEvent::RunSummary {
+                    duration: 9.0,
+                    wave: 1,
+                    shots: 3,
+                    asteroids_destroyed: 1,
+                },
+            ),
+        ];
+        let report = TelemetryReport::from_records(&records);
+        assert_eq!(report.runs, 1);
+        assert_eq!(report.hits, 2);
+        assert_eq!(report.asteroids_destroyed, 1);
+        assert_eq!(report.times_to_kill, vec![2.0]);
+        assert_eq!(report.accuracy(), Some(2.0 / 3.0));
+        assert_eq!(summarize(&report.survival_times), Some((9.0, 9.0, 9.0)));
+    }
+}
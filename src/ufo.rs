@@ -0,0 +1,342 @@
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    asteroids::{SPAWN_RANGE_X, SPAWN_RANGE_Z},
+    collision_detection::{Collider, CollisionDamage},
+    config::GameConfig,
+    console::ConsoleAppExt,
+    despawn::PlayArea,
+    health::Health,
+    movement::{Acceleration, Velocity},
+    pool::{PoolCommandsExt, SpawnPooled},
+    rng::GameRng,
+    spaceship::{Spaceship, SpaceshipMissile},
+    state::GameState,
+    steering,
+};
+
+const MAX_UFOS: usize = 2;
+const SPAWN_TIMER_SECONDS: f32 = 20.0;
+const MAX_SPEED: f32 = 12.0;
+const MAX_FORCE: f32 = 20.0;
+const RADIUS: f32 = 3.0;
+const HEALTH: f32 = 60.0;
+const COLLISION_DAMAGE: f32 = 50.0;
+// Closer than this the UFO stops chasing and circles around instead.
+const ENGAGE_DISTANCE: f32 = 30.0;
+const EVADE_DISTANCE: f32 = 15.0;
+const WANDER_JITTER: f32 = 4.0;
+const SPIN_SPEED: f32 = 3.0;
+const FIRE_SECONDS: f32 = 1.5;
+const FIRE_RANGE: f32 = 60.0;
+// The widest miss, reached when `ufo_accuracy` is 0.
+const MAX_SPREAD: f32 = PI / 6.0;
+const PROJECTILE_SPEED: f32 = 30.0;
+pub const PROJECTILE_RADIUS: f32 = 0.6;
+const PROJECTILE_HEALTH: f32 = 1.0;
+const PROJECTILE_COLLISION_DAMAGE: f32 = 15.0;
+const HULL_COLOR: Color = Color::rgb(0.6, 0.62, 0.68);
+const DOME_COLOR: Color = Color::rgb(0.3, 1.0, 0.5);
+const PROJECTILE_COLOR: Color = Color::rgb(1.0, 0.25, 0.2);
+
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SteeringMode {
+    #[default]
+    Seek,
+    Wander,
+    Evade,
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+pub struct Ufo {
+    pub mode: SteeringMode,
+    wander_angle: f32,
+    fire_timer: Timer,
+}
+
+impl Default for Ufo {
+    fn default() -> Self {
+        Self {
+            mode: SteeringMode::default(),
+            wander_angle: 0.0,
+            fire_timer: Timer::from_seconds(FIRE_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct UfoProjectile;
+
+#[derive(Resource, Debug, Default)]
+pub struct UfoAssets {
+    ufo: Handle<Scene>,
+    projectile: Handle<Scene>,
+}
+
+#[derive(Resource, Debug)]
+struct UfoSpawnTimer {
+    timer: Timer,
+}
+
+impl Default for UfoSpawnTimer {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(SPAWN_TIMER_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+pub struct UfoPlugin;
+
+impl Plugin for UfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Ufo>()
+            .register_type::<UfoProjectile>()
+            .init_resource::<UfoAssets>()
+            .init_resource::<UfoSpawnTimer>()
+            .add_systems(Startup, build_ufo_assets)
+            .add_systems(
+                Update,
+                (spawn_ufo, steer_ufos, ufo_weapons).run_if(in_state(GameState::InGame)),
+            )
+            .add_console_command(
+                "spawn ufo",
+                "spawn ufo <x> <z>: spawn an enemy ship at a position",
+                spawn_ufo_command,
+            );
+    }
+}
+
+fn build_ufo_assets(
+    mut ufo_assets: ResMut<UfoAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut scenes: ResMut<Assets<Scene>>,
+) {
+    let mut world = World::new();
+    world
+        .spawn(SpatialBundle::default())
+        .with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::UVSphere {
+                    radius: RADIUS,
+                    ..default()
+                })),
+                material: materials.add(StandardMaterial {
+                    base_color: HULL_COLOR,
+                    metallic: 0.8,
+                    perceptual_roughness: 0.3,
+                    ..default()
+                }),
+                transform: Transform::from_scale(Vec3::new(1.0, 0.3, 1.0)),
+                ..default()
+            });
+            parent.spawn(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::UVSphere {
+                    radius: RADIUS * 0.4,
+                    ..default()
+                })),
+                material: materials.add(StandardMaterial {
+                    base_color: DOME_COLOR,
+                    emissive: DOME_COLOR,
+                    ..default()
+                }),
+                transform: Transform::from_xyz(0.0, RADIUS * 0.2, 0.0),
+                ..default()
+            });
+        });
+    ufo_assets.ufo = scenes.add(Scene::new(world));
+
+    let mut world = World::new();
+    world.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: PROJECTILE_RADIUS,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: PROJECTILE_COLOR,
+            emissive: PROJECTILE_COLOR,
+            unlit: true,
+            ..default()
+        }),
+        ..default()
+    });
+    ufo_assets.projectile = scenes.add(Scene::new(world));
+}
+
+fn ufo_spawn(ufo_assets: &UfoAssets, translation: Vec3) -> SpawnPooled<impl Bundle> {
+    SpawnPooled {
+        scene: ufo_assets.ufo.clone(),
+        transform: Transform::from_translation(translation),
+        bundle: (
+            Velocity::new(Vec3::ZERO),
+            Acceleration::new(Vec3::ZERO),
+            Collider::new(RADIUS),
+            Ufo::default(),
+            Health::new(HEALTH),
+            CollisionDamage::new(COLLISION_DAMAGE),
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_ufo(
+    mut commands: Commands,
+    mut spawn_timer: ResMut<UfoSpawnTimer>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    ufo_assets: Res<UfoAssets>,
+    query: Query<(), With<Ufo>>,
+    play_area: Res<PlayArea>,
+    mut rng: ResMut<GameRng>,
+) {
+    spawn_timer
+        .timer
+        .set_duration(Duration::from_secs_f32(config.ufo_spawn_seconds));
+    spawn_timer.timer.tick(time.delta());
+    if !spawn_timer.timer.just_finished() || query.iter().count() >= MAX_UFOS {
+        return;
+    }
+
+    // Come in from one of the side edges of the asteroid field.
+    let x = if rng.gen_bool(0.5) {
+        SPAWN_RANGE_X.start
+    } else {
+        SPAWN_RANGE_X.end
+    };
+    let translation = Vec3::new(x, 0.0, rng.gen_range(SPAWN_RANGE_Z));
+    commands.spawn_pooled(ufo_spawn(&ufo_assets, play_area.center + translation));
+}
+
+type ThreatFilter = (With<SpaceshipMissile>, Without<Ufo>);
+
+fn steer_ufos(
+    mut ufo_query: Query<(&mut Ufo, &mut Transform, &mut Velocity, &mut Acceleration)>,
+    spaceship_query: Query<&Transform, (With<Spaceship>, Without<Ufo>)>,
+    missile_query: Query<(&Transform, &Velocity), ThreatFilter>,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+) {
+    for (mut ufo, mut transform, mut velocity, mut acceleration) in ufo_query.iter_mut() {
+        let position = transform.translation;
+        let target = steering::nearest(
+            position,
+            spaceship_query
+                .iter()
+                .map(|transform| (transform.translation, ())),
+        )
+        .map(|(target, _)| target);
+        let threat = missile_query
+            .iter()
+            .map(|(transform, velocity)| (transform.translation, velocity.value))
+            .filter(|(missile, _)| missile.distance(position) < EVADE_DISTANCE)
+            .min_by(|(a, _), (b, _)| a.distance(position).total_cmp(&b.distance(position)));
+
+        let steering = match (threat, target) {
+            (Some((missile, missile_velocity)), _) => {
+                ufo.mode = SteeringMode::Evade;
+                steering::evade(
+                    position,
+                    velocity.value,
+                    missile,
+                    missile_velocity,
+                    MAX_SPEED,
+                )
+            }
+            (None, Some(target)) if position.distance(target) > ENGAGE_DISTANCE => {
+                ufo.mode = SteeringMode::Seek;
+                steering::seek(position, velocity.value, target, MAX_SPEED)
+            }
+            _ => {
+                ufo.mode = SteeringMode::Wander;
+                ufo.wander_angle +=
+                    rng.gen_range(-WANDER_JITTER..WANDER_JITTER) * time.delta_seconds();
+                steering::wander(velocity.value, ufo.wander_angle, MAX_SPEED)
+            }
+        };
+        acceleration.value = steering.clamp_length_max(MAX_FORCE);
+        velocity.value = velocity.value.clamp_length_max(MAX_SPEED);
+        transform.rotate_y(SPIN_SPEED * time.delta_seconds());
+    }
+}
+
+fn ufo_weapons(
+    mut commands: Commands,
+    mut ufo_query: Query<(&mut Ufo, &Transform)>,
+    spaceship_query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    ufo_assets: Res<UfoAssets>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+) {
+    for (mut ufo, transform) in ufo_query.iter_mut() {
+        ufo.fire_timer.tick(time.delta());
+        let position = transform.translation;
+        let Some((target, target_velocity)) = steering::nearest(
+            position,
+            spaceship_query
+                .iter()
+                .map(|(transform, velocity)| (transform.translation, velocity.value)),
+        ) else {
+            continue;
+        };
+        if !ufo.fire_timer.just_finished() || position.distance(target) > FIRE_RANGE {
+            continue;
+        }
+
+        // Lead the target by the projectile's travel time, then miss by up to the spread.
+        let travel_time = position.distance(target) / PROJECTILE_SPEED;
+        let aim = target + target_velocity * travel_time - position;
+        let spread = (1.0 - config.ufo_accuracy.clamp(0.0, 1.0)) * MAX_SPREAD;
+        let error = if spread > 0.0 {
+            rng.gen_range(-spread..spread)
+        } else {
+            0.0
+        };
+        let direction =
+            Quat::from_rotation_y(error) * Vec3::new(aim.x, 0.0, aim.z).normalize_or_zero();
+
+        commands.spawn_pooled(enemy_projectile(
+            &ufo_assets,
+            position + direction * (RADIUS + PROJECTILE_RADIUS + 0.5),
+            direction,
+        ));
+    }
+}
+
+// Shared with the boss, which fires the same projectiles.
+pub fn enemy_projectile(
+    ufo_assets: &UfoAssets,
+    translation: Vec3,
+    direction: Vec3,
+) -> SpawnPooled<impl Bundle> {
+    SpawnPooled {
+        scene: ufo_assets.projectile.clone(),
+        transform: Transform::from_translation(translation),
+        bundle: (
+            Velocity::new(direction * PROJECTILE_SPEED),
+            Acceleration::new(Vec3::ZERO),
+            Collider::new(PROJECTILE_RADIUS),
+            UfoProjectile,
+            Health::new(PROJECTILE_HEALTH),
+            CollisionDamage::new(PROJECTILE_COLLISION_DAMAGE),
+        ),
+    }
+}
+
+fn spawn_ufo_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [x, z] = args else {
+        return Err("usage: spawn ufo <x> <z>".into());
+    };
+    let x: f32 = x.parse().map_err(|_| format!("'{x}' is not a number"))?;
+    let z: f32 = z.parse().map_err(|_| format!("'{z}' is not a number"))?;
+
+    let spawn = ufo_spawn(world.resource::<UfoAssets>(), Vec3::new(x, 0.0, z));
+    let entity = spawn.spawn(world);
+    Ok(format!("spawned ufo {entity:?}"))
+}
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+
+use crate::{config::GameConfig, console::ConsoleAppExt, state::GameState};
+
+const SPAWN_RATE_STEP: f32 = 0.15;
+
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct Wave {
+    pub number: u32,
+    starting_number: u32,
+    elapsed: f32,
+}
+
+impl Wave {
+    pub fn new(number: u32) -> Self {
+        let number = number.max(1);
+        Self {
+            number,
+            starting_number: number,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn set(&mut self, number: u32) {
+        self.number = number.max(1);
+        self.elapsed = 0.0;
+    }
+
+    // How many times faster than wave 1 asteroids are spawned.
+    pub fn spawn_rate(&self) -> f32 {
+        1.0 + (self.number - 1) as f32 * SPAWN_RATE_STEP
+    }
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wave>()
+            .register_type::<Wave>()
+            .add_systems(Update, advance_wave.run_if(in_state(GameState::InGame)))
+            .add_systems(OnEnter(GameState::GameOver), reset_wave)
+            .add_console_command("wave", "wave <number>: jump to a wave", wave_command);
+    }
+}
+
+fn advance_wave(mut wave: ResMut<Wave>, config: Res<GameConfig>, time: Res<Time>) {
+    wave.elapsed += time.delta_seconds();
+    if wave.elapsed >= config.wave_seconds {
+        let next = wave.number + 1;
+        wave.set(next);
+        info!("Wave {}", wave.number);
+    }
+}
+
+fn reset_wave(mut wave: ResMut<Wave>) {
+    let starting_number = wave.starting_number;
+    wave.set(starting_number);
+}
+
+fn wave_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let [number] = args else {
+        return Err("usage: wave <number>".into());
+    };
+    let number: u32 = number
+        .parse()
+        .map_err(|_| format!("'{number}' is not a wave number"))?;
+    world.resource_mut::<Wave>().set(number);
+    Ok(format!(
+        "jumped to wave {}",
+        world.resource::<Wave>().number
+    ))
+}